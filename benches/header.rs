@@ -0,0 +1,107 @@
+use byteorder::{BigEndian, ReadBytesExt};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use someip_messages::*;
+use std::io::Cursor;
+
+const MESSAGE: &[u8] = &[
+    0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+    0x00, 0x00, 0x00, 0x0D, // length(u32)
+    0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+    0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+    0x01, 0x02, 0x03, 0x04, 0x05, // payload
+];
+
+/// Header decoding through a `Cursor` and `ReadBytesExt` as done by earlier releases
+fn header_from_cursor(input: &[u8]) -> Result<Header, Error> {
+    let mut reader = Cursor::new(input);
+    let service_id = reader.read_u16::<BigEndian>()?;
+    let method_id = reader.read_u16::<BigEndian>()?;
+    let length = reader.read_u32::<BigEndian>()?;
+    let client_id = reader.read_u16::<BigEndian>()?;
+    let session_id = reader.read_u16::<BigEndian>()?;
+    let protocol_version = reader.read_u8()?;
+    let interface_version = reader.read_u8()?;
+    let message_type = MessageType::try_from(reader.read_u8()?)?;
+    let return_code = ReturnCode::try_from(reader.read_u8()?)?;
+    if length < 8 {
+        return Err(Error::InvalidLengthField(length));
+    }
+
+    Ok(Header::new(
+        MessageId::new(service_id, method_id),
+        length,
+        RequestId::new(client_id, session_id),
+        protocol_version,
+        interface_version,
+        message_type,
+        return_code,
+    ))
+}
+
+/// Message decoding matching the decoded header against the magic cookies
+fn message_from_cursor(input: &[u8]) -> Result<Message<'_>, Error> {
+    let header = header_from_cursor(input)?;
+    let cookie = |method_id, message_type| {
+        Header::new(
+            MessageId::new(0xFFFF, method_id),
+            8,
+            RequestId::new(0xDEAD, 0xBEEF),
+            0x01,
+            0x01,
+            message_type,
+            ReturnCode::Ok,
+        )
+    };
+    if header == cookie(0x0000, MessageType::RequestNoReturn) {
+        return Ok(Message::CookieClient);
+    }
+    if header == cookie(0x8000, MessageType::Notification) {
+        return Ok(Message::CookieServer);
+    }
+
+    let message_len = header.message_len();
+    if input.len() < message_len {
+        return Err(Error::NotEnoughData {
+            min: message_len,
+            actual: input.len(),
+        });
+    }
+    Ok(Message::Rpc(header, &input[Header::LENGTH..message_len]))
+}
+
+fn header(c: &mut Criterion) {
+    let mut group = c.benchmark_group("header");
+    let bytes: &[u8; 16] = MESSAGE.first_chunk().unwrap();
+
+    group.bench_function("cursor", |b| {
+        b.iter(|| header_from_cursor(black_box(MESSAGE)))
+    });
+    group.bench_function("from_slice", |b| {
+        b.iter(|| Header::from_slice(black_box(MESSAGE)))
+    });
+    group.bench_function("from_bytes", |b| {
+        b.iter(|| Header::from_bytes(black_box(bytes)))
+    });
+    group.bench_function("header_ref", |b| {
+        b.iter(|| {
+            let header = HeaderRef::from_slice(black_box(MESSAGE)).unwrap();
+            (header.message_id(), header.request_id(), header.length())
+        })
+    });
+    group.finish();
+}
+
+fn message(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message");
+
+    group.bench_function("cursor", |b| {
+        b.iter(|| message_from_cursor(black_box(MESSAGE)))
+    });
+    group.bench_function("from_slice", |b| {
+        b.iter(|| Message::from_slice(black_box(MESSAGE)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, header, message);
+criterion_main!(benches);
@@ -0,0 +1,237 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{meta::ParseNestedMeta, Attribute, LitInt, LitStr, Result};
+
+/// Largest TLV data id, the tag holds 12 bits of it
+const MAX_DATA_ID: u16 = 0x0FFF;
+
+/// Attributes of a struct or enum.
+#[derive(Default)]
+pub struct Container {
+    /// Length field of the struct or union
+    pub length_field: Option<TokenStream>,
+    /// TLV encoded struct
+    pub tlv: bool,
+    /// Type field of the union
+    pub type_field: Option<TokenStream>,
+    /// Alignment of union members in bytes
+    pub alignment: Option<usize>,
+    /// Byte order of the members
+    pub byte_order: Option<TokenStream>,
+    /// Alignment in bytes of members following variable size members
+    pub payload_alignment: Option<usize>,
+}
+
+impl Container {
+    pub fn from_struct(attrs: &[Attribute]) -> Result<Self> {
+        let mut container = Container::default();
+        parse(attrs, |meta| {
+            if meta.path.is_ident("length_field") {
+                container.length_field = Some(length_field(&meta, true)?.0);
+            } else if meta.path.is_ident("tlv") {
+                container.tlv = true;
+            } else if !container.parse_layout(&meta)? {
+                return Err(meta.error(
+                    "unsupported struct attribute, expected `length_field`, `tlv`, `byte_order` or `payload_alignment`",
+                ));
+            }
+            Ok(())
+        })?;
+        Ok(container)
+    }
+
+    pub fn from_enum(attrs: &[Attribute]) -> Result<Self> {
+        let mut container = Container::default();
+        parse(attrs, |meta| {
+            if meta.path.is_ident("length_field") {
+                container.length_field = Some(length_field(&meta, true)?.0);
+            } else if meta.path.is_ident("type_field") {
+                container.type_field = Some(length_field(&meta, false)?.0);
+            } else if meta.path.is_ident("alignment") {
+                match meta.value()?.parse::<LitInt>()?.base10_parse()? {
+                    0 => return Err(meta.error("the alignment must be at least 1")),
+                    alignment => container.alignment = Some(alignment),
+                }
+            } else if !container.parse_layout(&meta)? {
+                return Err(meta.error(
+                    "unsupported enum attribute, expected `length_field`, `type_field`, `alignment`, `byte_order` or `payload_alignment`",
+                ));
+            }
+            Ok(())
+        })?;
+        Ok(container)
+    }
+
+    /// Parses the attributes shared by structs and enums, `false` if the attribute is none of them.
+    fn parse_layout(&mut self, meta: &ParseNestedMeta) -> Result<bool> {
+        if meta.path.is_ident("byte_order") {
+            self.byte_order = Some(match meta.value()?.parse::<LitStr>()?.value().as_str() {
+                "big_endian" => quote!(BigEndian),
+                "little_endian" => quote!(LittleEndian),
+                _ => return Err(meta.error("expected `big_endian` or `little_endian`")),
+            });
+        } else if meta.path.is_ident("payload_alignment") {
+            match meta.value()?.parse::<LitInt>()?.base10_parse()? {
+                0 => return Err(meta.error("the payload alignment must be at least 1")),
+                alignment => self.payload_alignment = Some(alignment),
+            }
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Statement shadowing `options` with the byte order and payload alignment of the container
+    pub fn options(&self) -> TokenStream {
+        let byte_order = self.byte_order.as_ref().map(
+            |byte_order| quote!(byte_order: ::someip_messages::payload::ByteOrder::#byte_order,),
+        );
+        let alignment = self
+            .payload_alignment
+            .map(|alignment| quote!(alignment: #alignment,));
+        if byte_order.is_none() && alignment.is_none() {
+            return TokenStream::new();
+        }
+        quote! {
+            let options = &::someip_messages::payload::FieldOptions {
+                #byte_order
+                #alignment
+                ..*options
+            };
+        }
+    }
+}
+
+/// Attributes of a field.
+#[derive(Default)]
+pub struct Field {
+    /// Length field and its size in bits
+    pub length_field: Option<(TokenStream, u8)>,
+    /// Character encoding of strings
+    pub encoding: Option<TokenStream>,
+    /// Size of fixed length strings
+    pub fixed_size: Option<usize>,
+    /// Smallest number of array elements
+    pub min_elements: Option<usize>,
+    /// Largest number of array elements
+    pub max_elements: Option<usize>,
+    /// Data id of TLV members
+    pub data_id: Option<u16>,
+}
+
+impl Field {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut field = Field::default();
+        parse(attrs, |meta| {
+            if meta.path.is_ident("length_field") {
+                field.length_field = Some(length_field(&meta, true)?);
+            } else if meta.path.is_ident("encoding") {
+                field.encoding = Some(match meta.value()?.parse::<LitStr>()?.value().as_str() {
+                    "utf8" => quote!(Utf8),
+                    "utf16be" => quote!(Utf16Be),
+                    "utf16le" => quote!(Utf16Le),
+                    _ => return Err(meta.error("expected `utf8`, `utf16be` or `utf16le`")),
+                });
+            } else if meta.path.is_ident("fixed_size") {
+                field.fixed_size = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("min_elements") {
+                field.min_elements = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("max_elements") {
+                field.max_elements = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("data_id") {
+                match meta.value()?.parse::<LitInt>()?.base10_parse()? {
+                    data_id if data_id > MAX_DATA_ID => {
+                        return Err(meta.error("the data id must be at most 0x0FFF"))
+                    }
+                    data_id => field.data_id = Some(data_id),
+                }
+            } else {
+                return Err(meta.error("unsupported field attribute"));
+            }
+
+            if field.fixed_size.is_some() && field.length_field.is_some() {
+                return Err(meta.error("`fixed_size` and `length_field` conflict"));
+            }
+            if let (Some(min), Some(max)) = (field.min_elements, field.max_elements) {
+                if min > max {
+                    return Err(meta.error("`min_elements` exceeds `max_elements`"));
+                }
+            }
+            Ok(())
+        })?;
+        Ok(field)
+    }
+
+    /// Expression of the `FieldOptions` of the field at `offset` of the input,
+    /// inheriting byte order and alignment of `options`
+    pub fn options(&self, offset: TokenStream) -> TokenStream {
+        let length_field = option(self.length_field.as_ref().map(
+            |(length_field, _)| quote!(::someip_messages::payload::LengthField::#length_field),
+        ));
+        let encoding = self.encoding.clone().unwrap_or(quote!(Utf8));
+        let fixed_size = option(self.fixed_size.map(|size| quote!(#size)));
+        let min_elements = self.min_elements.unwrap_or_default();
+        let max_elements = option(self.max_elements.map(|max| quote!(#max)));
+        quote! {
+            ::someip_messages::payload::FieldOptions {
+                length_field: #length_field,
+                encoding: ::someip_messages::payload::Encoding::#encoding,
+                fixed_size: #fixed_size,
+                min_elements: #min_elements,
+                max_elements: #max_elements,
+                ..::someip_messages::payload::__private::member(options, #offset)
+            }
+        }
+    }
+}
+
+/// Attributes of an enum variant.
+#[derive(Default)]
+pub struct Variant {
+    /// Type selector of the union member
+    pub selector: Option<u32>,
+}
+
+impl Variant {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut variant = Variant::default();
+        parse(attrs, |meta| {
+            if meta.path.is_ident("selector") {
+                variant.selector = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else {
+                return Err(meta.error("unsupported variant attribute, expected `selector`"));
+            }
+            Ok(())
+        })?;
+        Ok(variant)
+    }
+}
+
+/// Parses the `#[someip(...)]` attributes.
+fn parse(attrs: &[Attribute], mut f: impl FnMut(ParseNestedMeta) -> Result<()>) -> Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("someip")) {
+        attr.parse_nested_meta(&mut f)?;
+    }
+    Ok(())
+}
+
+/// Parses the size of a length field in bits into a `LengthField` variant.
+fn length_field(meta: &ParseNestedMeta, allow_none: bool) -> Result<(TokenStream, u8)> {
+    let bits = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+    let variant = match bits {
+        0 if allow_none => quote!(None),
+        8 => quote!(U8),
+        16 => quote!(U16),
+        32 => quote!(U32),
+        _ if allow_none => return Err(meta.error("expected a size of 0, 8, 16 or 32 bits")),
+        _ => return Err(meta.error("expected a size of 8, 16 or 32 bits")),
+    };
+    Ok((variant, bits))
+}
+
+fn option(value: Option<TokenStream>) -> TokenStream {
+    match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    }
+}
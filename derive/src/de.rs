@@ -0,0 +1,192 @@
+use crate::{input_lifetime, Field, Layout, Style};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, DeriveInput, GenericParam};
+
+pub fn expand(input: &DeriveInput, layout: &Layout) -> TokenStream {
+    let ident = &input.ident;
+    let (lifetime, fresh) = input_lifetime(input);
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::someip_messages::payload::SomeIpDeserialize<#lifetime>));
+    }
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    if fresh {
+        generics
+            .params
+            .insert(0, GenericParam::Lifetime(parse_quote!(#lifetime)));
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let body = match layout {
+        Layout::Struct {
+            container,
+            fields,
+            style,
+        } if container.tlv => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(U32));
+            let data_ids = fields.iter().map(|field| field.attr.data_id);
+            let members = fields.iter().map(|field| {
+                let var = &field.var;
+                let ty = field.optional.as_ref().unwrap_or(&field.ty);
+                let data_id = field.attr.data_id;
+                let segment = field.segment();
+                let options = field.attr.options(quote!(0));
+                let decode = quote! {
+                    ::someip_messages::payload::__private::decode_member::<#ty>(
+                        input, &members, #data_id, #segment, &#options,
+                    )?
+                };
+                match field.optional {
+                    Some(_) => quote!(let #var = #decode;),
+                    None => quote! {
+                        let ::core::option::Option::Some(#var) = #decode else {
+                            return ::core::result::Result::Err(
+                                ::someip_messages::Error::MissingDataId(#data_id),
+                            );
+                        };
+                    },
+                }
+            });
+            let value = construct(fields, *style);
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let codec = ::someip_messages::payload::TlvCodec {
+                    length_field: options
+                        .length_field
+                        .unwrap_or(::someip_messages::payload::LengthField::#length_field),
+                    byte_order: options.byte_order,
+                    ..::core::default::Default::default()
+                };
+                let (members, len) = codec.decode(input, &[#(#data_ids),*])?;
+                #(#members)*
+                let len = ::someip_messages::payload::__private::padded_len(options, len, input);
+                ::core::result::Result::Ok((#value, len))
+            }
+        }
+        Layout::Struct {
+            container,
+            fields,
+            style,
+        } => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(None));
+            let members = fields.iter().map(|field| {
+                let var = &field.var;
+                let ty = &field.ty;
+                let options = field.attr.options(quote!(offset));
+                let segment = field.segment();
+                quote! {
+                    let (#var, len) = <#ty as ::someip_messages::payload::SomeIpDeserialize<#lifetime>>::decode(
+                        &#options,
+                        &input[offset..range.end],
+                    )
+                    .map_err(|error| ::someip_messages::payload::__private::context(error, offset, #segment))?;
+                    let offset = offset + len;
+                }
+            });
+            let value = construct(fields, *style);
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let length_field = options
+                    .length_field
+                    .unwrap_or(::someip_messages::payload::LengthField::#length_field);
+                let range = ::someip_messages::payload::__private::delimit(length_field, options, input)?;
+                let offset = range.start;
+                #(#members)*
+                let len = match length_field {
+                    ::someip_messages::payload::LengthField::None => offset,
+                    _ => ::someip_messages::payload::__private::padded_len(options, range.end, input),
+                };
+                ::core::result::Result::Ok((#value, len))
+            }
+        }
+        Layout::Enum {
+            container,
+            variants,
+        } => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(U32));
+            let type_field = container.type_field.clone().unwrap_or(quote!(U32));
+            let alignment = container.alignment.unwrap_or(1);
+            let arms = variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                let selector = variant.selector;
+                let name = variant_ident.to_string();
+                match &variant.field {
+                    Some(field) => {
+                        let ty = &field.ty;
+                        let options = field.attr.options(quote!(start));
+                        quote! {
+                            #selector => {
+                                let (value, len) = <#ty as ::someip_messages::payload::SomeIpDeserialize<#lifetime>>::decode(
+                                    &#options,
+                                    data,
+                                )
+                                .map_err(|error| {
+                                    ::someip_messages::payload::__private::context(
+                                        error,
+                                        start,
+                                        ::someip_messages::PathSegment::Field(#name),
+                                    )
+                                })?;
+                                (Self::#variant_ident(value), len)
+                            }
+                        }
+                    }
+                    None => quote!(#selector => (Self::#variant_ident, 0),),
+                }
+            });
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let codec = ::someip_messages::payload::UnionCodec {
+                    length_field: options
+                        .length_field
+                        .unwrap_or(::someip_messages::payload::LengthField::#length_field),
+                    type_field: ::someip_messages::payload::LengthField::#type_field,
+                    alignment: #alignment,
+                    byte_order: options.byte_order,
+                };
+                let start = codec.length_field.size() + codec.type_field.size();
+                ::someip_messages::payload::__private::decode_union(&codec, options, input, |selector, data| {
+                    ::core::result::Result::Ok(match selector {
+                        #(#arms)*
+                        selector => {
+                            return ::core::result::Result::Err(
+                                ::someip_messages::Error::InvalidTypeSelector(selector),
+                            )
+                        }
+                    })
+                })
+            }
+        }
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::someip_messages::payload::SomeIpDeserialize<#lifetime> for #ident #ty_generics #where_clause {
+            fn decode(
+                options: &::someip_messages::payload::FieldOptions,
+                input: &#lifetime [u8],
+            ) -> ::core::result::Result<(Self, usize), ::someip_messages::Error> {
+                #body
+            }
+        }
+    }
+}
+
+/// Expression constructing `Self` from the local variables of the fields.
+fn construct(fields: &[Field], style: Style) -> TokenStream {
+    let vars = fields.iter().map(|field| &field.var);
+    match style {
+        Style::Named => {
+            let members = fields.iter().map(|field| &field.member);
+            quote!(Self { #(#members: #vars),* })
+        }
+        Style::Unnamed => quote!(Self(#(#vars),*)),
+        Style::Unit => quote!(Self),
+    }
+}
@@ -0,0 +1,349 @@
+//! Derive macros for SOME/IP payload types.
+//!
+//! `#[derive(SomeIpSerialize, SomeIpDeserialize)]` implements the traits of the same
+//! name of `someip_messages::payload`, usually enabled with the `derive` feature of
+//! `someip-messages`:
+//!
+//! ```
+//! use someip_messages::payload::{SomeIpDeserialize, SomeIpSerialize};
+//!
+//! #[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+//! struct Status<'a> {
+//!     id: u16,
+//!     #[someip(length_field = 8)]
+//!     name: &'a str,
+//!     #[someip(max_elements = 4)]
+//!     values: Vec<u32>,
+//! }
+//!
+//! let status = Status { id: 1, name: "a", values: vec![2] };
+//! let payload = status.to_payload().unwrap();
+//! assert_eq!(Ok(status), Status::from_payload(&payload));
+//! ```
+//!
+//! Structs are encoded member by member, optionally preceded by a length field.
+//! Structs with the `tlv` attribute are TLV encoded, each field is tagged with its
+//! data id. Enums are encoded as unions, each variant is either a unit variant or
+//! has a single unnamed field.
+//!
+//! # Attributes
+//!
+//! Structs:
+//! - `length_field = 0 | 8 | 16 | 32`: size of the length field in bits,
+//!   defaults to 0 for plain and to 32 for TLV encoded structs
+//! - `tlv`: TLV encoded struct
+//!
+//! Enums:
+//! - `length_field = 0 | 8 | 16 | 32`: size of the length field in bits, defaults to 32
+//! - `type_field = 8 | 16 | 32`: size of the type field in bits, defaults to 32
+//! - `alignment = N`: alignment of the members in bytes, defaults to 1
+//!
+//! Structs and enums:
+//! - `byte_order = "big_endian" | "little_endian"`: byte order of numbers, length
+//!   and type fields, inherited from the enclosing type and defaults to big endian
+//! - `payload_alignment = N`: strings, dynamic length arrays, unions and structs
+//!   with a length field are padded to a multiple of `N` bytes from the start of
+//!   the payload, inherited from the enclosing type and defaults to 1
+//!
+//! Variants:
+//! - `selector = N`: type selector, defaults to the variant index plus one.
+//!   The selector 0 denotes the empty union and is only allowed for unit variants.
+//!
+//! Fields:
+//! - `length_field = 0 | 8 | 16 | 32`: size of the length field of strings,
+//!   arrays and nested structs and unions in bits. Selects the wire type of
+//!   TLV members.
+//! - `encoding = "utf8" | "utf16be" | "utf16le"`: character encoding of strings
+//! - `fixed_size = N`: size of fixed length strings in bytes
+//! - `min_elements = N`, `max_elements = N`: bounds of arrays
+//! - `data_id = N`: data id of TLV members, at most 0x0FFF. Members of type
+//!   `Option<T>` may be missing, missing required members are an error.
+//!
+//! Layouts without SOME/IP representation are rejected at compile time:
+//!
+//! ```compile_fail
+//! # use someip_messages::payload::SomeIpSerialize;
+//! #[derive(SomeIpSerialize)]
+//! enum Value {
+//!     Pair(u8, u8),
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use someip_messages::payload::SomeIpSerialize;
+//! #[derive(SomeIpSerialize)]
+//! struct Value {
+//!     #[someip(length_field = 24)]
+//!     name: String,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! # use someip_messages::payload::SomeIpSerialize;
+//! #[derive(SomeIpSerialize)]
+//! #[someip(tlv)]
+//! struct Value {
+//!     #[someip(data_id = 1)]
+//!     a: u8,
+//!     #[someip(data_id = 1)]
+//!     b: u8,
+//! }
+//! ```
+
+mod attr;
+mod de;
+mod ser;
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, Ident,
+    Lifetime, Member, PathArguments, Result, Type,
+};
+
+/// Derives `SomeIpSerialize` for structs and enums.
+#[proc_macro_derive(SomeIpSerialize, attributes(someip))]
+pub fn derive_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    Layout::parse(&input)
+        .map(|layout| ser::expand(&input, &layout))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `SomeIpDeserialize` for structs and enums.
+#[proc_macro_derive(SomeIpDeserialize, attributes(someip))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    Layout::parse(&input)
+        .map(|layout| de::expand(&input, &layout))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Validated layout of a struct or enum.
+enum Layout {
+    Struct {
+        container: attr::Container,
+        fields: Vec<Field>,
+        style: Style,
+    },
+    Enum {
+        container: attr::Container,
+        variants: Vec<Variant>,
+    },
+}
+
+/// Field of a struct or union member.
+struct Field {
+    member: Member,
+    /// Local variable holding the value
+    var: Ident,
+    ty: Type,
+    /// Type of the value of `Option<T>` fields of TLV encoded structs
+    optional: Option<Type>,
+    attr: attr::Field,
+}
+
+impl Field {
+    /// Expression of the `PathSegment` of the field
+    fn segment(&self) -> proc_macro2::TokenStream {
+        match &self.member {
+            Member::Named(ident) => {
+                let name = ident.to_string();
+                quote!(::someip_messages::PathSegment::Field(#name))
+            }
+            Member::Unnamed(index) => {
+                let index = index.index as usize;
+                quote!(::someip_messages::PathSegment::Index(#index))
+            }
+        }
+    }
+}
+
+/// Union member.
+struct Variant {
+    ident: Ident,
+    selector: u32,
+    field: Option<Field>,
+}
+
+#[derive(Copy, Clone)]
+enum Style {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+impl Layout {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        if input.generics.lifetimes().count() > 1 {
+            return Err(Error::new_spanned(
+                &input.generics,
+                "at most one lifetime parameter is supported",
+            ));
+        }
+
+        match &input.data {
+            Data::Struct(data) => {
+                let container = attr::Container::from_struct(&input.attrs)?;
+                let style = match &data.fields {
+                    Fields::Named(_) => Style::Named,
+                    Fields::Unnamed(_) => Style::Unnamed,
+                    Fields::Unit => Style::Unit,
+                };
+                let fields = parse_fields(&data.fields)?;
+
+                let mut data_ids = Vec::new();
+                for field in &fields {
+                    match (container.tlv, field.attr.data_id) {
+                        (true, None) => {
+                            return Err(Error::new_spanned(
+                                &field.member,
+                                "members of TLV encoded structs require a `data_id`",
+                            ))
+                        }
+                        (true, Some(data_id)) if data_ids.contains(&data_id) => {
+                            return Err(Error::new_spanned(
+                                &field.member,
+                                format!("duplicate data id {data_id}"),
+                            ))
+                        }
+                        (true, Some(data_id)) => data_ids.push(data_id),
+                        (false, Some(_)) => {
+                            return Err(Error::new_spanned(
+                                &field.member,
+                                "`data_id` requires a TLV encoded struct, add `#[someip(tlv)]`",
+                            ))
+                        }
+                        (false, None) => {}
+                    }
+                    if container.tlv && matches!(&field.attr.length_field, Some((_, 0))) {
+                        return Err(Error::new_spanned(
+                            &field.member,
+                            "TLV members are delimited by 8, 16 or 32 bit length fields",
+                        ));
+                    }
+                    if !container.tlv && field.optional.is_some() {
+                        return Err(Error::new_spanned(
+                            &field.ty,
+                            "optional members require a TLV encoded struct, add `#[someip(tlv)]`",
+                        ));
+                    }
+                }
+
+                Ok(Layout::Struct {
+                    container,
+                    fields,
+                    style,
+                })
+            }
+            Data::Enum(data) => {
+                let container = attr::Container::from_enum(&input.attrs)?;
+                if data.variants.is_empty() {
+                    return Err(Error::new_spanned(
+                        &input.ident,
+                        "unions require at least one member",
+                    ));
+                }
+                let mut variants: Vec<Variant> = Vec::new();
+                for (index, variant) in data.variants.iter().enumerate() {
+                    let attr = attr::Variant::parse(&variant.attrs)?;
+                    let field = match &variant.fields {
+                        Fields::Unit => None,
+                        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            parse_fields(&variant.fields)?.pop()
+                        }
+                        _ => return Err(Error::new_spanned(
+                            variant,
+                            "union members must be unit variants or have a single unnamed field",
+                        )),
+                    };
+                    if let Some(field) = &field {
+                        if field.attr.data_id.is_some() || field.optional.is_some() {
+                            return Err(Error::new_spanned(
+                                &field.ty,
+                                "union members cannot be TLV members",
+                            ));
+                        }
+                    }
+
+                    let selector = attr.selector.unwrap_or(index as u32 + 1);
+                    if selector == 0 && field.is_some() {
+                        return Err(Error::new_spanned(
+                            variant,
+                            "the type selector 0 denotes the empty union and requires a unit variant",
+                        ));
+                    }
+                    if variants.iter().any(|other| other.selector == selector) {
+                        return Err(Error::new_spanned(
+                            variant,
+                            format!("duplicate type selector {selector}"),
+                        ));
+                    }
+                    variants.push(Variant {
+                        ident: variant.ident.clone(),
+                        selector,
+                        field,
+                    });
+                }
+                Ok(Layout::Enum {
+                    container,
+                    variants,
+                })
+            }
+            Data::Union(data) => Err(Error::new(
+                data.union_token.span(),
+                "Rust unions are not supported, use an enum for SOME/IP unions",
+            )),
+        }
+    }
+}
+
+fn parse_fields(fields: &Fields) -> Result<Vec<Field>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let attr = attr::Field::parse(&field.attrs)?;
+            Ok(Field {
+                member: match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                },
+                var: format_ident!("__field{}", index),
+                ty: field.ty.clone(),
+                optional: option_value(&field.ty),
+                attr,
+            })
+        })
+        .collect()
+}
+
+/// Type of the value of `Option<T>`.
+fn option_value(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The lifetime of the input, the lifetime parameter of the type if any.
+fn input_lifetime(input: &DeriveInput) -> (Lifetime, bool) {
+    match input.generics.lifetimes().next() {
+        Some(param) => (param.lifetime.clone(), false),
+        None => (Lifetime::new("'__de", proc_macro2::Span::call_site()), true),
+    }
+}
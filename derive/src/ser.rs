@@ -0,0 +1,138 @@
+use crate::Layout;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, DeriveInput};
+
+pub fn expand(input: &DeriveInput, layout: &Layout) -> TokenStream {
+    let ident = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::someip_messages::payload::SomeIpSerialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match layout {
+        Layout::Struct {
+            container, fields, ..
+        } if container.tlv => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(U32));
+            let members = fields.iter().map(|field| {
+                let member = &field.member;
+                let options = field.attr.options(quote!(0));
+                let data_id = field.attr.data_id;
+                let encode = quote! {
+                    ::someip_messages::payload::__private::encode_member(
+                        &mut members, #data_id, value, &#options,
+                    )?;
+                };
+                match field.optional {
+                    Some(_) => quote!(if let ::core::option::Option::Some(value) = &self.#member { #encode }),
+                    None => quote!({ let value = &self.#member; #encode }),
+                }
+            });
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let codec = ::someip_messages::payload::TlvCodec {
+                    length_field: options
+                        .length_field
+                        .unwrap_or(::someip_messages::payload::LengthField::#length_field),
+                    byte_order: options.byte_order,
+                    ..::core::default::Default::default()
+                };
+                let mut members = ::someip_messages::payload::__private::Vec::new();
+                #(#members)*
+                ::someip_messages::payload::__private::encode_tlv(&codec, &members, options, output)
+            }
+        }
+        Layout::Struct {
+            container, fields, ..
+        } => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(None));
+            let members = fields.iter().map(|field| {
+                let member = &field.member;
+                let options = field.attr.options(quote!(0));
+                quote! {
+                    ::someip_messages::payload::SomeIpSerialize::encode(&self.#member, &#options, output)?;
+                }
+            });
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let length_field = options
+                    .length_field
+                    .unwrap_or(::someip_messages::payload::LengthField::#length_field);
+                ::someip_messages::payload::__private::encode_struct(
+                    length_field,
+                    options,
+                    output,
+                    |output| {
+                        #(#members)*
+                        ::core::result::Result::Ok(())
+                    },
+                )
+            }
+        }
+        Layout::Enum {
+            container,
+            variants,
+        } => {
+            let length_field = container.length_field.clone().unwrap_or(quote!(U32));
+            let type_field = container.type_field.clone().unwrap_or(quote!(U32));
+            let alignment = container.alignment.unwrap_or(1);
+            let arms = variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                let selector = variant.selector;
+                match &variant.field {
+                    Some(field) => {
+                        let options = field.attr.options(quote!(0));
+                        quote! {
+                            Self::#variant_ident(value) => {
+                                ::someip_messages::payload::__private::encode_union(&codec, #selector, options, output, |output| {
+                                    ::someip_messages::payload::SomeIpSerialize::encode(value, &#options, output)
+                                })
+                            }
+                        }
+                    }
+                    None => quote! {
+                        Self::#variant_ident => {
+                            ::someip_messages::payload::__private::encode_union(&codec, #selector, options, output, |_| {
+                                ::core::result::Result::Ok(())
+                            })
+                        }
+                    },
+                }
+            });
+            let container_options = container.options();
+            quote! {
+                #container_options
+                let codec = ::someip_messages::payload::UnionCodec {
+                    length_field: options
+                        .length_field
+                        .unwrap_or(::someip_messages::payload::LengthField::#length_field),
+                    type_field: ::someip_messages::payload::LengthField::#type_field,
+                    alignment: #alignment,
+                    byte_order: options.byte_order,
+                };
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::someip_messages::payload::SomeIpSerialize for #ident #ty_generics #where_clause {
+            fn encode(
+                &self,
+                options: &::someip_messages::payload::FieldOptions,
+                output: &mut ::someip_messages::payload::__private::Vec<u8>,
+            ) -> ::core::result::Result<(), ::someip_messages::Error> {
+                #body
+            }
+        }
+    }
+}
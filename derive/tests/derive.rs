@@ -0,0 +1,315 @@
+use someip_messages::{
+    payload::{FieldOptions, LengthField, SomeIpDeserialize, SomeIpSerialize},
+    Error, Message, MessageId, RequestId,
+};
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+struct Status<'a> {
+    id: u16,
+    #[someip(length_field = 8)]
+    name: &'a str,
+    #[someip(length_field = 16, max_elements = 3)]
+    values: Vec<u8>,
+    active: bool,
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(length_field = 16)]
+struct Wrapper(u8, Point);
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(length_field = 8)]
+struct Point {
+    x: i16,
+    y: i16,
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(tlv, length_field = 16)]
+struct Extensible {
+    #[someip(data_id = 1)]
+    number: u32,
+    #[someip(data_id = 2, length_field = 8, encoding = "utf16be")]
+    text: String,
+    #[someip(data_id = 0x0FFF)]
+    point: Option<Point>,
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(length_field = 8, type_field = 8, alignment = 4)]
+enum Value {
+    #[someip(selector = 0)]
+    Empty,
+    Number(u16),
+    Text(#[someip(fixed_size = 8)] String),
+    #[someip(selector = 10)]
+    Point(Point),
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(length_field = 0, type_field = 8, alignment = 4)]
+enum Shape {
+    Circle(u8),
+    Square(u16),
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+struct Drawing {
+    shape: Shape,
+    color: u8,
+    corners: [u8; 2],
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(byte_order = "little_endian", payload_alignment = 4)]
+struct Sample<'a> {
+    id: u16,
+    #[someip(length_field = 8)]
+    name: &'a str,
+    values: Vec<u16>,
+    point: Point,
+}
+
+#[derive(Debug, PartialEq, SomeIpSerialize, SomeIpDeserialize)]
+#[someip(length_field = 8)]
+struct Samples {
+    #[someip(length_field = 16)]
+    values: Vec<u8>,
+}
+
+#[test]
+fn plain_struct() {
+    let expected: &[u8] = &[
+        0x12, 0x34, // id(u16)
+        0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), name
+        0x00, 0x02, 0x01, 0x02, // length(u16), values
+        0x01, // active(bool)
+    ];
+    let status = Status {
+        id: 0x1234,
+        name: "a",
+        values: vec![1, 2],
+        active: true,
+    };
+
+    assert_eq!(Ok(expected.to_vec()), status.to_payload());
+    assert_eq!(Ok(status), Status::from_payload(expected));
+}
+
+#[test]
+fn nested_structs_with_length_fields() {
+    let expected: &[u8] = &[
+        0x00, 0x06, // length(u16)
+        0x07, // 0(u8)
+        0x04, 0x00, 0x01, 0xFF, 0xFF, // length(u8), x(i16), y(i16)
+    ];
+    let wrapper = Wrapper(7, Point { x: 1, y: -1 });
+    assert_eq!(Ok(expected.to_vec()), wrapper.to_payload());
+    assert_eq!(Ok(wrapper), Wrapper::from_payload(expected));
+
+    // members appended by newer versions are skipped
+    let extended: &[u8] = &[
+        0x06, // length(u8)
+        0x00, 0x01, 0x00, 0x02, // x(i16), y(i16)
+        0xAA, 0xBB, // unknown member
+    ];
+    assert_eq!(
+        Ok((Point { x: 1, y: 2 }, 7)),
+        Point::decode(&FieldOptions::default(), extended)
+    );
+
+    // the field options override the length field of the struct
+    let options = FieldOptions {
+        length_field: Some(LengthField::None),
+        ..FieldOptions::default()
+    };
+    let mut output = Vec::new();
+    Point { x: 1, y: 2 }.encode(&options, &mut output).unwrap();
+    assert_eq!(vec![0x00, 0x01, 0x00, 0x02], output);
+}
+
+#[test]
+fn output_unchanged_on_error() {
+    let mut output = vec![0xAA];
+    let samples = Samples {
+        values: vec![0; 0x100],
+    };
+    assert_eq!(
+        Err(Error::LengthFieldOverflow {
+            len: 0x102,
+            max: 0xFF
+        }),
+        samples.encode(&FieldOptions::default(), &mut output)
+    );
+    assert_eq!(vec![0xAA], output);
+}
+
+#[test]
+fn tlv_struct() {
+    let expected: &[u8] = &[
+        0x00, 0x19, // length(u16)
+        0x20, 0x01, 0x00, 0x00, 0x00, 0x2A, // tag(fixed32, 1), number(u32)
+        0x50, 0x02, 0x06, 0xFE, 0xFF, 0x00, 0x61, 0x00,
+        0x00, // tag(dynamic8, 2), length(u8), text
+        0x7F, 0xFF, 0x00, 0x00, 0x00, 0x04, // tag(dynamic32, 0xFFF), length(u32)
+        0x00, 0x01, 0x00, 0x02, // point
+    ];
+    let value = Extensible {
+        number: 42,
+        text: "a".into(),
+        point: Some(Point { x: 1, y: 2 }),
+    };
+    assert_eq!(Ok(expected.to_vec()), value.to_payload());
+    assert_eq!(Ok(value), Extensible::from_payload(expected));
+
+    // optional members may be missing, members are accepted in any order
+    let input: &[u8] = &[
+        0x00, 0x0F, // length(u16)
+        0x50, 0x02, 0x06, 0xFE, 0xFF, 0x00, 0x61, 0x00,
+        0x00, // tag(dynamic8, 2), length(u8), text
+        0x20, 0x01, 0x00, 0x00, 0x00, 0x2A, // tag(fixed32, 1), number(u32)
+    ];
+    let value = Extensible {
+        number: 42,
+        text: "a".into(),
+        point: None,
+    };
+    assert_eq!(Ok(value), Extensible::from_payload(input));
+
+    assert_eq!(
+        Err(Error::MissingDataId(2)),
+        Extensible::from_payload(&[0x00, 0x06, 0x20, 0x01, 0x00, 0x00, 0x00, 0x2A])
+    );
+}
+
+#[test]
+fn enum_as_union() {
+    let expected: &[u8] = &[
+        0x04, // length(u8)
+        0x02, // type(u8)
+        0x12, 0x34, 0x00, 0x00, // member(u16), padding
+    ];
+    assert_eq!(Ok(expected.to_vec()), Value::Number(0x1234).to_payload());
+    assert_eq!(Ok(Value::Number(0x1234)), Value::from_payload(expected));
+
+    let expected: &[u8] = &[
+        0x08, // length(u8)
+        0x0A, // type(u8)
+        0x04, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, // length(u8), point, padding
+    ];
+    let point = Value::Point(Point { x: 1, y: 2 });
+    assert_eq!(Ok(expected.to_vec()), point.to_payload());
+    assert_eq!(Ok(point), Value::from_payload(expected));
+
+    assert_eq!(Ok(vec![0x00, 0x00]), Value::Empty.to_payload());
+    assert_eq!(Ok(Value::Empty), Value::from_payload(&[0x00, 0x00]));
+    assert_eq!(
+        Ok(Value::Text("ab".into())),
+        Value::from_payload(&[0x08, 0x03, 0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00, 0x00, 0x00])
+    );
+    assert_eq!(
+        Err(Error::InvalidTypeSelector(4)),
+        Value::from_payload(&[0x00, 0x04])
+    );
+}
+
+#[test]
+fn union_without_length_field() {
+    let expected: &[u8] = &[
+        0x01, // type(u8)
+        0x07, 0x00, 0x00, 0x00, // member(u8), padding
+        0x09, // color(u8)
+        0x01, 0x02, // corners
+    ];
+    let drawing = Drawing {
+        shape: Shape::Circle(7),
+        color: 9,
+        corners: [1, 2],
+    };
+    assert_eq!(Ok(expected.to_vec()), drawing.to_payload());
+    assert_eq!(Ok(drawing), Drawing::from_payload(expected));
+}
+
+#[test]
+fn byte_order_and_alignment() {
+    let expected: &[u8] = &[
+        0x34, 0x12, // id(u16)
+        0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), name
+        0x02, 0x00, 0x00, 0x00, 0x01, 0x00, // length(u32), values
+        0x00, 0x00, // padding
+        0x04, 0x01, 0x00, 0xFF, 0xFF, // length(u8), x(i16), y(i16)
+        0x00, 0x00, 0x00, // padding
+    ];
+    let sample = Sample {
+        id: 0x1234,
+        name: "a",
+        values: vec![1],
+        point: Point { x: 1, y: -1 },
+    };
+    assert_eq!(Ok(expected.to_vec()), sample.to_payload());
+    assert_eq!(Ok(sample), Sample::from_payload(expected));
+}
+
+#[test]
+fn fixed_length_arrays() {
+    let options = FieldOptions {
+        max_elements: Some(3),
+        ..FieldOptions::default()
+    };
+    assert_eq!(
+        Err(Error::TooManyElements { max: 2, actual: 3 }),
+        <[u8; 2]>::decode(&options, &[0x01, 0x02, 0x03])
+    );
+    assert_eq!(
+        Err(Error::NotEnoughData { min: 2, actual: 1 }),
+        <[u8; 2]>::decode(&FieldOptions::default(), &[0x01])
+    );
+}
+
+#[test]
+fn errors_carry_the_field_path() {
+    let input: &[u8] = &[
+        0x00, 0x01, // id(u16)
+        0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), name
+        0x00, 0x04, 0x01, 0x02, 0x03, 0x04, // length(u16), values
+        0x00, // active(bool)
+    ];
+    let error = Status::from_payload(input).unwrap_err();
+    assert_eq!(
+        &Error::TooManyElements { max: 3, actual: 4 },
+        error.root_cause()
+    );
+    assert_eq!(Some(8), error.offset());
+    assert_eq!("values", error.path().unwrap().to_string());
+
+    let error = Wrapper::from_payload(&[0x00, 0x04, 0x07, 0x04, 0x00, 0x01]).unwrap_err();
+    assert_eq!(
+        &Error::NotEnoughData { min: 5, actual: 3 },
+        error.root_cause()
+    );
+    assert_eq!(Some(3), error.offset());
+    assert_eq!("[1]", error.path().unwrap().to_string());
+}
+
+#[test]
+fn payload_of_rpc_messages() {
+    let status = Status {
+        id: 1,
+        name: "abc",
+        values: vec![],
+        active: false,
+    };
+    let payload = status.to_payload().unwrap();
+    let message = Message::notification(
+        MessageId::new(0x0103, 0x8005),
+        RequestId::new(0x0001, 0x0002),
+        0x01,
+        &payload,
+    );
+    let bytes = message.to_vec().unwrap();
+
+    match Message::from_slice(&bytes).unwrap() {
+        Message::Rpc(_, payload) => assert_eq!(Ok(status), Status::from_payload(payload)),
+        message => panic!("unexpected message {message:?}"),
+    }
+}
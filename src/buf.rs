@@ -0,0 +1,212 @@
+use crate::{
+    serializer::{Sink, MAGIC_COOKIE_CLIENT, MAGIC_COOKIE_SERVER},
+    types::*,
+    Error,
+};
+use bytes::{BufMut, Bytes};
+
+/// Counterpart of [`Message`] whose RPC payload is a reference counted
+/// slice of the [`Bytes`] it was parsed from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BytesMessage {
+    /// RPC Message
+    Rpc(Header, Bytes),
+    /// SD Message
+    Sd(Header, SdPayload),
+    /// Magic Cookie Client
+    /// RS_SOMEIP_00010
+    CookieClient,
+    /// Magic Cookie Server
+    /// RS_SOMEIP_00010
+    CookieServer,
+}
+
+impl BytesMessage {
+    /// Parse a message from `Bytes` without copying the payload.
+    pub fn from_bytes(input: &Bytes) -> Result<BytesMessage, Error> {
+        Ok(match Message::from_slice(input)? {
+            Message::Rpc(header, payload) => BytesMessage::Rpc(header, input.slice_ref(payload)),
+            Message::Sd(header, payload) => BytesMessage::Sd(header, payload),
+            Message::CookieClient => BytesMessage::CookieClient,
+            Message::CookieServer => BytesMessage::CookieServer,
+        })
+    }
+
+    /// Get the header of the message (if not a magic cookie)
+    pub fn header(&self) -> Option<&Header> {
+        match self {
+            BytesMessage::Rpc(header, _) | BytesMessage::Sd(header, _) => Some(header),
+            BytesMessage::CookieClient | BytesMessage::CookieServer => None,
+        }
+    }
+
+    /// Borrow the message as a [`Message`].
+    pub fn as_message(&self) -> Message<'_> {
+        match self {
+            BytesMessage::Rpc(header, payload) => Message::Rpc(header.clone(), payload),
+            BytesMessage::Sd(header, payload) => Message::Sd(header.clone(), payload.clone()),
+            BytesMessage::CookieClient => Message::CookieClient,
+            BytesMessage::CookieServer => Message::CookieServer,
+        }
+    }
+
+    /// Serialize the message into a `BufMut`.
+    pub fn to_buf<B: BufMut>(&self, buf: &mut B) -> Result<usize, Error> {
+        match self {
+            BytesMessage::Rpc(header, payload) => Message::Rpc(header.clone(), payload).to_buf(buf),
+            BytesMessage::Sd(header, payload) => {
+                header.validate_payload_len(payload.len())?;
+                let len = Header::LENGTH + payload.len();
+                let mut sink = BufSink::new(buf, len)?;
+                header.encode(&mut sink);
+                payload.encode(&mut sink);
+                Ok(len)
+            }
+            BytesMessage::CookieClient => Message::CookieClient.to_buf(buf),
+            BytesMessage::CookieServer => Message::CookieServer.to_buf(buf),
+        }
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Serialize the message into a `BufMut`.
+    pub fn to_buf<B: BufMut>(&self, buf: &mut B) -> Result<usize, Error> {
+        self.validate()?;
+        let len = self.len();
+        let mut sink = BufSink::new(buf, len)?;
+
+        match self {
+            Message::Rpc(header, payload) => {
+                header.encode(&mut sink);
+                sink.put(payload);
+            }
+            Message::Sd(header, payload) => {
+                header.encode(&mut sink);
+                payload.encode(&mut sink);
+            }
+            Message::CookieClient => MAGIC_COOKIE_CLIENT.encode(&mut sink),
+            Message::CookieServer => MAGIC_COOKIE_SERVER.encode(&mut sink),
+        }
+
+        Ok(len)
+    }
+}
+
+impl Header {
+    /// Serialize the header into a `BufMut`.
+    pub fn to_buf<B: BufMut>(&self, buf: &mut B) -> Result<usize, Error> {
+        self.encode(&mut BufSink::new(buf, Header::LENGTH)?);
+        Ok(Header::LENGTH)
+    }
+}
+
+impl SdPayload {
+    /// Serialize the payload into a `BufMut`.
+    pub fn to_buf<B: BufMut>(&self, buf: &mut B) -> Result<usize, Error> {
+        let len = self.len();
+        self.encode(&mut BufSink::new(buf, len)?);
+        Ok(len)
+    }
+}
+
+/// Writes big endian values into a `BufMut`.
+struct BufSink<'b, B> {
+    buf: &'b mut B,
+}
+
+impl<'b, B: BufMut> BufSink<'b, B> {
+    fn new(buf: &'b mut B, required: usize) -> Result<Self, Error> {
+        if buf.remaining_mut() < required {
+            return Err(Error::BufferTooSmall {
+                required,
+                available: buf.remaining_mut(),
+            });
+        }
+
+        Ok(Self { buf })
+    }
+}
+
+impl<'b, B: BufMut> Sink for BufSink<'b, B> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf.put_slice(bytes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::BytesMut;
+
+    const RPC_MESSAGE: &[u8] = &[
+        0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x0D, // length(u32)
+        0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+        0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        0x01, 0x02, 0x03, 0x04, 0x05, // payload
+    ];
+
+    const SD_MESSAGE: &[u8] = &[
+        0xFF, 0xFF, 0x81, 0x00, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x14, // length(u32)
+        0x00, 0x00, 0x00, 0x01, // clientId(u16), sessionId(u16)
+        0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        0xC0, 0x00, 0x00, 0x00, // sdFlags(08), reserved(u24)
+        0x00, 0x00, 0x00, 0x00, // entries-length(u32)
+        0x00, 0x00, 0x00, 0x00, // options-length(u32)
+    ];
+
+    #[test]
+    fn parse_rpc_message_from_bytes() {
+        let input = Bytes::from_static(RPC_MESSAGE);
+
+        let message = BytesMessage::from_bytes(&input).unwrap();
+        match &message {
+            BytesMessage::Rpc(header, payload) => {
+                assert_eq!(13, header.length);
+                assert_eq!(&RPC_MESSAGE[16..], payload);
+                assert_eq!(input[16..].as_ptr(), payload.as_ptr());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let mut buf = BytesMut::new();
+        assert_eq!(21, message.to_buf(&mut buf).unwrap());
+        assert_eq!(RPC_MESSAGE, buf);
+    }
+
+    #[test]
+    fn sd_message_to_buf() {
+        let input = Bytes::from_static(SD_MESSAGE);
+        let message = BytesMessage::from_bytes(&input).unwrap();
+        assert!(matches!(message, BytesMessage::Sd(_, _)));
+
+        let mut buf = BytesMut::new();
+        assert_eq!(28, message.to_buf(&mut buf).unwrap());
+        message.as_message().to_buf(&mut buf).unwrap();
+        Message::CookieClient.to_buf(&mut buf).unwrap();
+        assert_eq!(
+            [
+                SD_MESSAGE,
+                SD_MESSAGE,
+                &Message::CookieClient.to_vec().unwrap()
+            ]
+            .concat(),
+            buf
+        );
+    }
+
+    #[test]
+    fn to_buf_too_small() {
+        let mut buffer = [0u8; 20];
+        let message = Message::from_slice(RPC_MESSAGE).unwrap();
+
+        assert_eq!(
+            Err(Error::BufferTooSmall {
+                required: 21,
+                available: 20
+            }),
+            message.to_buf(&mut &mut buffer[..])
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use crate::{types::*, Error};
+
+/// Builder for RPC messages which computes the length field from the payload.
+///
+/// Complements the [`HeaderBuilder`], whose `length` is set by [`MessageBuilder::build`].
+#[derive(Clone, Default)]
+pub struct MessageBuilder<'a> {
+    header: HeaderBuilder,
+    payload: RpcPayload<'a>,
+}
+
+impl<'a> MessageBuilder<'a> {
+    /// Set message id
+    pub fn message_id(&mut self, value: MessageId) -> &mut Self {
+        self.header.message_id(value);
+        self
+    }
+
+    /// Set request id
+    pub fn request_id(&mut self, value: RequestId) -> &mut Self {
+        self.header.request_id(value);
+        self
+    }
+
+    /// Set protocol version (defaults to 1)
+    pub fn protocol_version(&mut self, value: ProtocolVersion) -> &mut Self {
+        self.header.protocol_version(value);
+        self
+    }
+
+    /// Set interface version
+    pub fn interface_version(&mut self, value: InterfaceVersion) -> &mut Self {
+        self.header.interface_version(value);
+        self
+    }
+
+    /// Set message type
+    pub fn message_type(&mut self, value: MessageType) -> &mut Self {
+        self.header.message_type(value);
+        self
+    }
+
+    /// Set return code
+    pub fn return_code(&mut self, value: ReturnCode) -> &mut Self {
+        self.header.return_code(value);
+        self
+    }
+
+    /// Set payload (defaults to an empty payload)
+    pub fn payload(&mut self, value: RpcPayload<'a>) -> &mut Self {
+        self.payload = value;
+        self
+    }
+
+    /// Builds the message with the length field matching the payload.
+    pub fn build(&self) -> Result<Message<'a>, Error> {
+        let mut header = self.header.clone();
+        header.length(length_for(self.payload.len()));
+        let header = header.build().map_err(|error| match error {
+            HeaderBuilderError::UninitializedField(field) => Error::UninitializedField(field),
+            HeaderBuilderError::ValidationError(error) => Error::InvalidBuilderFields(error),
+        })?;
+
+        Ok(Message::Rpc(header, self.payload))
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Creates a builder for RPC messages.
+    pub fn builder() -> MessageBuilder<'a> {
+        MessageBuilder::default()
+    }
+
+    /// Creates a request expecting a response.
+    pub fn request(
+        message_id: MessageId,
+        request_id: RequestId,
+        interface_version: InterfaceVersion,
+        payload: RpcPayload<'a>,
+    ) -> Self {
+        Message::rpc(
+            message_id,
+            request_id,
+            interface_version,
+            MessageType::Request,
+            payload,
+        )
+    }
+
+    /// Creates a notification.
+    pub fn notification(
+        message_id: MessageId,
+        request_id: RequestId,
+        interface_version: InterfaceVersion,
+        payload: RpcPayload<'a>,
+    ) -> Self {
+        Message::rpc(
+            message_id,
+            request_id,
+            interface_version,
+            MessageType::Notification,
+            payload,
+        )
+    }
+
+    fn rpc(
+        message_id: MessageId,
+        request_id: RequestId,
+        interface_version: InterfaceVersion,
+        message_type: MessageType,
+        payload: RpcPayload<'a>,
+    ) -> Self {
+        Message::Rpc(
+            Header::new(
+                message_id,
+                length_for(payload.len()),
+                request_id,
+                0x01,
+                interface_version,
+                message_type,
+                ReturnCode::Ok,
+            ),
+            payload,
+        )
+    }
+
+    /// Creates the response to a request.
+    ///
+    /// Message id, request id and versions are copied from the request header.
+    /// Fails if the request does not expect a response.
+    pub fn response_for(request: &Header, payload: RpcPayload<'a>) -> Result<Self, Error> {
+        let message_type = match request.message_type.normalize() {
+            MessageType::Request => MessageType::Response,
+            MessageType::TpRequest => MessageType::TpResponse,
+            message_type => return Err(Error::UnexpectedMessageType(message_type)),
+        };
+
+        Ok(Message::reply(
+            request,
+            message_type,
+            ReturnCode::Ok,
+            payload,
+        ))
+    }
+
+    /// Creates the error response to a request.
+    ///
+    /// Message id, request id and versions are copied from the request header.
+    /// Fails if the request does not expect a response or the return code is `Ok`.
+    pub fn error_for(request: &Header, return_code: ReturnCode) -> Result<Self, Error> {
+        let message_type = match request.message_type.normalize() {
+            MessageType::Request => MessageType::Error,
+            MessageType::TpRequest => MessageType::TpError,
+            message_type => return Err(Error::UnexpectedMessageType(message_type)),
+        };
+        if return_code == ReturnCode::Ok {
+            return Err(Error::UnexpectedReturnCode(return_code));
+        }
+
+        Ok(Message::reply(request, message_type, return_code, &[]))
+    }
+
+    fn reply(
+        request: &Header,
+        message_type: MessageType,
+        return_code: ReturnCode,
+        payload: RpcPayload<'a>,
+    ) -> Self {
+        Message::Rpc(
+            Header::new(
+                request.message_id.clone(),
+                length_for(payload.len()),
+                request.request_id.clone(),
+                request.protocol_version,
+                request.interface_version,
+                message_type,
+                return_code,
+            ),
+            payload,
+        )
+    }
+
+    /// Creates a SD message with the fixed SD header values.
+    pub fn sd(session_id: SessionId, payload: SdPayload) -> Self {
+        Message::Sd(
+            Header::new(
+                MessageId::new(0xFFFF, 0x8100),
+                length_for(payload.len()),
+                RequestId::new(0x0000, session_id),
+                0x01,
+                0x01,
+                MessageType::Notification,
+                ReturnCode::Ok,
+            ),
+            payload,
+        )
+    }
+}
+
+/// Length field for a payload of the given length.
+///
+/// Oversized payloads wrap around and are rejected by [`Message::validate`].
+fn length_for(payload_len: usize) -> u32 {
+    (payload_len as u32).wrapping_add(8)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn build_rpc_message() {
+        let message = Message::builder()
+            .message_id(MessageId::new(0x0103, 0x8005))
+            .request_id(RequestId::new(0x0001, 0x0002))
+            .interface_version(0x01)
+            .message_type(MessageType::Notification)
+            .return_code(ReturnCode::Ok)
+            .payload(&[0x01, 0x02, 0x03])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            Message::notification(
+                MessageId::new(0x0103, 0x8005),
+                RequestId::new(0x0001, 0x0002),
+                0x01,
+                &[0x01, 0x02, 0x03]
+            ),
+            message
+        );
+        assert_eq!(Some(11), message.header().map(|header| header.length));
+        assert_eq!(
+            message,
+            Message::from_slice(&message.to_vec().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn build_uninitialized_field() {
+        assert_eq!(
+            Err(Error::UninitializedField("message_id")),
+            Message::builder().payload(&[0x01]).build()
+        );
+    }
+
+    #[test]
+    fn request_and_sd_constructors() {
+        let request = Message::request(
+            MessageId::new(0x0103, 0x0001),
+            RequestId::new(0x0001, 0x0002),
+            0x02,
+            &[],
+        );
+        assert!(matches!(
+            &request,
+            Message::Rpc(header, _)
+                if header.length == 8 && header.message_type == MessageType::Request
+        ));
+
+        let sd = Message::sd(
+            0x0001,
+            SdPayload {
+                flags: 0xC0,
+                entries: Vec::new(),
+                options: Vec::new(),
+            },
+        );
+        match &sd {
+            Message::Sd(header, _) => {
+                assert!(header.is_sd());
+                assert_eq!(20, header.length);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(sd, Message::from_slice(&sd.to_vec().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn serialize_invalid_length() {
+        let mut buffer = [0u8; 32];
+        let header = Header::new(
+            MessageId::new(0x0103, 0x0001),
+            9,
+            RequestId::new(0x0001, 0x0002),
+            0x01,
+            0x01,
+            MessageType::Request,
+            ReturnCode::Ok,
+        );
+        let message = Message::Rpc(header.clone(), &[0x01, 0x02]);
+        let error = Error::PayloadLengthMismatch {
+            header: 1,
+            payload: 2,
+        };
+
+        assert_eq!(Err(error.clone()), message.validate());
+        assert_eq!(Err(error.clone()), message.to_vec());
+        assert_eq!(Err(error.clone()), message.to_slice(&mut buffer));
+        assert_eq!(Err(error), message.to_writer(&mut buffer[..]));
+
+        let message = Message::Rpc(
+            Header {
+                length: 7,
+                ..header
+            },
+            &[],
+        );
+        assert_eq!(Err(Error::InvalidLengthField(7)), message.validate());
+    }
+
+    #[test]
+    fn response_and_error_for_request() {
+        let request = Message::request(
+            MessageId::new(0x0103, 0x0001),
+            RequestId::new(0x0001, 0x0002),
+            0x02,
+            &[0x01],
+        );
+        let header = request.header().unwrap();
+
+        let response = Message::response_for(header, &[0x01, 0x02]).unwrap();
+        let response_header = response.header().unwrap();
+        assert_eq!(header.message_id, response_header.message_id);
+        assert_eq!(header.request_id, response_header.request_id);
+        assert_eq!(0x02, response_header.interface_version);
+        assert_eq!(MessageType::Response, response_header.message_type);
+        assert_eq!(ReturnCode::Ok, response_header.return_code);
+        assert_eq!(Ok(()), response.validate());
+
+        let error = Message::error_for(header, ReturnCode::NotReady).unwrap();
+        let error_header = error.header().unwrap();
+        assert_eq!(MessageType::Error, error_header.message_type);
+        assert_eq!(ReturnCode::NotReady, error_header.return_code);
+        assert_eq!(8, error_header.length);
+
+        assert_eq!(
+            Err(Error::UnexpectedReturnCode(ReturnCode::Ok)),
+            Message::error_for(header, ReturnCode::Ok)
+        );
+        assert_eq!(
+            Err(Error::UnexpectedReturnCode(ReturnCode::Ok)),
+            Message::error_for(header, ReturnCode::Unknown(0x00))
+        );
+
+        // unknown values equal to known ones are treated alike
+        let lenient = Header {
+            message_type: MessageType::Unknown(0x00),
+            ..header.clone()
+        };
+        assert_eq!(Ok(response), Message::response_for(&lenient, &[0x01, 0x02]));
+        assert_eq!(
+            Ok(error),
+            Message::error_for(&lenient, ReturnCode::NotReady)
+        );
+    }
+
+    #[test]
+    fn response_for_tp_and_fire_and_forget() {
+        let mut request = Header::new(
+            MessageId::new(0x0103, 0x0001),
+            8,
+            RequestId::new(0x0001, 0x0002),
+            0x01,
+            0x01,
+            MessageType::TpRequest,
+            ReturnCode::Ok,
+        );
+
+        let response = Message::response_for(&request, &[]).unwrap();
+        assert_eq!(
+            Some(MessageType::TpResponse),
+            response.header().map(|header| header.message_type)
+        );
+        let error = Message::error_for(&request, ReturnCode::Timeout).unwrap();
+        assert_eq!(
+            Some(MessageType::TpError),
+            error.header().map(|header| header.message_type)
+        );
+
+        for message_type in [
+            MessageType::RequestNoReturn,
+            MessageType::TpRequestNoReturn,
+            MessageType::Notification,
+            MessageType::Response,
+        ] {
+            request.message_type = message_type;
+            assert_eq!(
+                Err(Error::UnexpectedMessageType(message_type)),
+                Message::response_for(&request, &[])
+            );
+            assert_eq!(
+                Err(Error::UnexpectedMessageType(message_type)),
+                Message::error_for(&request, ReturnCode::NotOk)
+            );
+        }
+    }
+}
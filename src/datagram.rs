@@ -0,0 +1,477 @@
+use crate::{types::*, Error};
+use alloc::boxed::Box;
+use core::iter::FusedIterator;
+
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// Iterator over the SOME/IP messages packed into a single datagram.
+///
+/// Each item holds the byte offset of the message within the datagram
+/// and the message itself, borrowed from the datagram.
+/// If the remaining bytes do not form a valid message, an
+/// [`Error::InvalidDatagram`] with the offset of the remainder is
+/// returned and the iteration ends.
+#[derive(Clone, Debug)]
+pub struct Messages<'a> {
+    input: &'a [u8],
+    mode: ParseMode,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> Messages<'a> {
+    /// Creates an iterator over the messages within a datagram.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_mode(input, ParseMode::Strict)
+    }
+
+    /// Creates an iterator over the messages within a datagram using the given parse mode.
+    pub fn with_mode(input: &'a [u8], mode: ParseMode) -> Self {
+        Self {
+            input,
+            mode,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// The offset of the next message within the datagram
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The bytes not yet consumed by the iterator
+    pub fn remainder(&self) -> &'a [u8] {
+        &self.input[self.offset..]
+    }
+
+    fn next_message(&self) -> Result<(usize, Message<'a>), Error> {
+        let input = self.remainder();
+        let message_len = Header::from_slice_with_mode(input, self.mode)?.message_len();
+        if input.len() < message_len {
+            return Err(Error::NotEnoughData {
+                min: message_len,
+                actual: input.len(),
+            });
+        }
+
+        let message = Message::from_slice_with_mode(&input[..message_len], self.mode)?;
+        Ok((message_len, message))
+    }
+}
+
+impl<'a> Iterator for Messages<'a> {
+    type Item = Result<(usize, Message<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset == self.input.len() {
+            return None;
+        }
+
+        let offset = self.offset;
+        match self.next_message() {
+            Ok((message_len, message)) => {
+                self.offset += message_len;
+                Some(Ok((offset, message)))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(Error::InvalidDatagram {
+                    offset,
+                    error: Box::new(error),
+                }))
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Messages<'a> {}
+
+impl<'a> Message<'a> {
+    /// Iterate over all messages packed into a byte slice, e.g. a UDP datagram.
+    pub fn iter_slice(input: &'a [u8]) -> Messages<'a> {
+        Messages::new(input)
+    }
+}
+
+#[cfg(feature = "std")]
+/// Defines until when a message added to a [`DatagramPacker`] must be sent.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Flush {
+    /// The datagram is sent right after the message has been added
+    Immediately,
+    /// The datagram is sent at the latest at the given deadline
+    Deadline(Instant),
+}
+
+#[cfg(feature = "std")]
+impl Flush {
+    /// Default flush policy of a message.
+    ///
+    /// Notifications (including SD messages) may be batched for up to `max_delay`,
+    /// all other messages are flushed immediately.
+    pub fn for_message(message: &Message, now: Instant, max_delay: Duration) -> Self {
+        match message {
+            Message::Rpc(header, _) | Message::Sd(header, _)
+                if matches!(
+                    header.message_type.normalize(),
+                    MessageType::Notification | MessageType::TpNotification
+                ) =>
+            {
+                Flush::Deadline(now + max_delay)
+            }
+            _ => Flush::Immediately,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Packs multiple SOME/IP messages into datagrams.
+///
+/// Messages are serialized into a caller-provided buffer whose size limits
+/// the size of each datagram (e.g. the MTU). A datagram is completed if the
+/// next message does not fit anymore, if a message must be flushed
+/// immediately, or if the earliest deadline of the contained messages has
+/// passed (see [`DatagramPacker::poll`]).
+#[derive(Debug)]
+pub struct DatagramPacker<B> {
+    buffer: B,
+    len: usize,
+    deadline: Option<Instant>,
+}
+
+#[cfg(feature = "std")]
+impl<B: AsRef<[u8]> + AsMut<[u8]>> DatagramPacker<B> {
+    /// Creates a new packer using the given buffer.
+    pub fn new(buffer: B) -> Self {
+        Self {
+            buffer,
+            len: 0,
+            deadline: None,
+        }
+    }
+
+    /// The maximum size of a datagram
+    pub fn mtu(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    /// The number of bytes within the pending datagram
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no message is pending
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The instant at which the pending datagram must be sent
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Adds a message at `now` and returns all datagrams which are complete afterwards.
+    ///
+    /// The pending datagram is completed before the message is added if its
+    /// deadline has passed. Fails if the message is invalid or larger than the
+    /// buffer, the pending datagram is left unchanged then.
+    pub fn push(
+        &mut self,
+        message: &Message,
+        flush: Flush,
+        now: Instant,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        // nothing may fail once datagrams have been taken out of the packer
+        message.validate()?;
+        let message_len = message.len();
+        if message_len > self.mtu() {
+            return Err(Error::BufferTooSmall {
+                required: message_len,
+                available: self.mtu(),
+            });
+        }
+
+        let mut datagrams: Vec<_> = self.poll(now).into_iter().collect();
+        if self.len + message_len > self.mtu() {
+            datagrams.extend(self.flush());
+        }
+
+        let buffer = &mut self.buffer.as_mut()[self.len..self.len + message_len];
+        self.len += message.to_writer(buffer)?;
+
+        match flush {
+            Flush::Immediately => datagrams.extend(self.flush()),
+            Flush::Deadline(deadline) => {
+                self.deadline = Some(match self.deadline {
+                    Some(current) => current.min(deadline),
+                    None => deadline,
+                });
+            }
+        }
+
+        Ok(datagrams)
+    }
+
+    /// Returns the pending datagram if its deadline has passed.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<u8>> {
+        match self.deadline {
+            Some(deadline) if deadline <= now => self.flush(),
+            _ => None,
+        }
+    }
+
+    /// Returns the pending datagram regardless of its deadline.
+    pub fn flush(&mut self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let datagram = self.buffer.as_ref()[..self.len].to_vec();
+        self.len = 0;
+        self.deadline = None;
+        Some(datagram)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RPC_MESSAGE: &[u8] = &[
+        0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x0A, // length(u32)
+        0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+        0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        0x01, 0x02, // payload
+    ];
+
+    const COOKIE_CLIENT: &[u8] = &[
+        0xFF, 0xFF, 0x00, 0x00, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x08, // length(u32)
+        0xDE, 0xAD, 0xBE, 0xEF, // clientId(u16), sessionId(u16)
+        0x01, 0x01, 0x01, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+    ];
+
+    #[test]
+    fn iter_empty_datagram() {
+        assert!(Message::iter_slice(&[]).next().is_none());
+    }
+
+    #[test]
+    fn iter_packed_datagram() {
+        let datagram: &[u8] = &[COOKIE_CLIENT, RPC_MESSAGE, RPC_MESSAGE].concat();
+
+        let messages: Vec<_> = Message::iter_slice(datagram)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(3, messages.len());
+        assert_eq!((0, Message::CookieClient), messages[0]);
+        assert_eq!(16, messages[1].0);
+        assert_eq!(34, messages[2].0);
+        assert!(matches!(messages[2].1, Message::Rpc(_, &[0x01, 0x02])));
+    }
+
+    #[test]
+    fn iter_truncated_datagram() {
+        let datagram: &[u8] = &[RPC_MESSAGE, &RPC_MESSAGE[..17]].concat();
+
+        let mut messages = Message::iter_slice(datagram);
+        assert_eq!(0, messages.next().unwrap().unwrap().0);
+        assert_eq!(
+            Some(Err(Error::InvalidDatagram {
+                offset: 18,
+                error: Box::new(Error::NotEnoughData {
+                    min: 18,
+                    actual: 17
+                })
+            })),
+            messages.next()
+        );
+        assert!(messages.next().is_none());
+        assert_eq!(18, messages.offset());
+        assert_eq!(17, messages.remainder().len());
+    }
+
+    #[test]
+    fn iter_datagram_with_trailing_garbage() {
+        let datagram: &[u8] = &[RPC_MESSAGE, &[0x00, 0x01, 0x02]].concat();
+
+        let mut messages = Message::iter_slice(datagram);
+        assert!(messages.next().unwrap().is_ok());
+        assert_eq!(
+            Some(Err(Error::InvalidDatagram {
+                offset: 18,
+                error: Box::new(Error::NotEnoughData { min: 16, actual: 3 })
+            })),
+            messages.next()
+        );
+        assert!(messages.next().is_none());
+    }
+
+    fn rpc_message(message_type: MessageType) -> Message<'static> {
+        Message::Rpc(
+            Header {
+                message_id: MessageId::new(0x0103, 0x8005),
+                length: 10,
+                request_id: RequestId::new(0x0001, 0x0002),
+                protocol_version: 0x01,
+                interface_version: 0x01,
+                message_type,
+                return_code: ReturnCode::Ok,
+            },
+            &[0x01, 0x02],
+        )
+    }
+
+    #[test]
+    fn pack_notifications_until_full() {
+        let now = Instant::now();
+        let notification = rpc_message(MessageType::Notification);
+        let flush = Flush::for_message(&notification, now, Duration::from_millis(10));
+        let mut packer = DatagramPacker::new([0u8; 40]);
+
+        assert!(packer.push(&notification, flush, now).unwrap().is_empty());
+        assert!(packer.push(&notification, flush, now).unwrap().is_empty());
+        assert_eq!(36, packer.len());
+
+        let datagrams = packer.push(&notification, flush, now).unwrap();
+        assert_eq!(vec![[RPC_MESSAGE, RPC_MESSAGE].concat()], datagrams);
+        assert_eq!(18, packer.len());
+        assert_eq!(Some(RPC_MESSAGE.to_vec()), packer.flush());
+        assert!(packer.is_empty());
+    }
+
+    #[test]
+    fn pack_request_flushes_immediately() {
+        let now = Instant::now();
+        let notification = rpc_message(MessageType::Notification);
+        let request = rpc_message(MessageType::Request);
+        let mut packer = DatagramPacker::new(vec![0u8; 1400]);
+
+        packer
+            .push(
+                &notification,
+                Flush::for_message(&notification, now, Duration::from_millis(10)),
+                now,
+            )
+            .unwrap();
+        let datagrams = packer
+            .push(
+                &request,
+                Flush::for_message(&request, now, Duration::from_millis(10)),
+                now,
+            )
+            .unwrap();
+
+        assert_eq!(1, datagrams.len());
+        assert_eq!(36, datagrams[0].len());
+        assert!(packer.is_empty());
+        assert_eq!(None, packer.deadline());
+    }
+
+    #[test]
+    fn flush_unknown_notification_types() {
+        let now = Instant::now();
+        let max_delay = Duration::from_millis(10);
+        assert_eq!(
+            Flush::Deadline(now + max_delay),
+            Flush::for_message(&rpc_message(MessageType::Unknown(0x22)), now, max_delay)
+        );
+        assert_eq!(
+            Flush::Immediately,
+            Flush::for_message(&rpc_message(MessageType::Unknown(0x23)), now, max_delay)
+        );
+    }
+
+    #[test]
+    fn pack_flushes_at_earliest_deadline() {
+        let now = Instant::now();
+        let notification = rpc_message(MessageType::Notification);
+        let mut packer = DatagramPacker::new(vec![0u8; 1400]);
+
+        let early = now + Duration::from_millis(5);
+        let late = now + Duration::from_millis(10);
+        packer
+            .push(&notification, Flush::Deadline(late), now)
+            .unwrap();
+        packer
+            .push(&notification, Flush::Deadline(early), now)
+            .unwrap();
+
+        assert_eq!(Some(early), packer.deadline());
+        assert_eq!(None, packer.poll(now));
+        assert_eq!(Some(36), packer.poll(early).map(|datagram| datagram.len()));
+        assert_eq!(None, packer.poll(late));
+    }
+
+    #[test]
+    fn pack_flushes_expired_datagram_before_push() {
+        let now = Instant::now();
+        let notification = rpc_message(MessageType::Notification);
+        let mut packer = DatagramPacker::new(vec![0u8; 1400]);
+
+        let deadline = now + Duration::from_millis(5);
+        let later = now + Duration::from_millis(10);
+        packer
+            .push(&notification, Flush::Deadline(deadline), now)
+            .unwrap();
+        let datagrams = packer
+            .push(
+                &notification,
+                Flush::Deadline(later + Duration::from_millis(5)),
+                later,
+            )
+            .unwrap();
+
+        assert_eq!(vec![RPC_MESSAGE.to_vec()], datagrams);
+        assert_eq!(18, packer.len());
+        assert_eq!(Some(later + Duration::from_millis(5)), packer.deadline());
+    }
+
+    #[test]
+    fn pack_invalid_message_keeps_expired_datagram() {
+        let now = Instant::now();
+        let notification = rpc_message(MessageType::Notification);
+        let mut packer = DatagramPacker::new(vec![0u8; 1400]);
+        packer
+            .push(&notification, Flush::Deadline(now), now)
+            .unwrap();
+
+        let mut invalid = rpc_message(MessageType::Notification);
+        if let Message::Rpc(header, _) = &mut invalid {
+            header.length = 11;
+        }
+        let later = now + Duration::from_millis(10);
+        assert_eq!(
+            Err(Error::PayloadLengthMismatch {
+                header: 3,
+                payload: 2
+            }),
+            packer.push(&invalid, Flush::Immediately, later)
+        );
+        assert_eq!(Some(RPC_MESSAGE.to_vec()), packer.poll(later));
+    }
+
+    #[test]
+    fn pack_message_larger_than_mtu() {
+        let mut packer = DatagramPacker::new([0u8; 16]);
+
+        assert_eq!(
+            Err(Error::BufferTooSmall {
+                required: 18,
+                available: 16
+            }),
+            packer.push(
+                &rpc_message(MessageType::Request),
+                Flush::Immediately,
+                Instant::now()
+            )
+        );
+        assert!(packer.is_empty());
+    }
+}
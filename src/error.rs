@@ -1,32 +1,78 @@
-use std::io;
+use crate::{MessageType, MethodId, ReturnCode, ServiceId};
 use thiserror::Error;
 
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
 /// Custom Error type
-#[derive(Error, Debug)]
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
 pub enum Error {
     /// IO Error
+    #[cfg(feature = "std")]
     #[error("IO Error: {0}")]
-    Io(io::Error),
-    /// A string or sequence with a minimum size (min_size for strings and min_elements for sequences)
+    Io(io::ErrorKind),
+    /// The input ended before the end of a field,
+    /// or a string or sequence with a minimum size (min_size for strings and min_elements for sequences)
     /// was deserialized and contained less data than the minimum size.
     #[error("Not enough data: min: {min}, actual: {actual}")]
     NotEnoughData {
-        /// The minimum size required.
-        /// For strings this is in bytes for sequences in elements.
+        /// The minimum size required, i.e. the offset of the end of the truncated field.
+        /// Payload members report the size of the truncated field, for sequences in elements.
         min: usize,
-        /// The actual size received.
-        /// For strings this is in bytes for sequences in elements.
+        /// The actual size received, i.e. the offset of the end of the input.
+        /// Payload members report the bytes left for the truncated field, for sequences in elements.
         actual: usize,
     },
     /// Invalid length field value
     #[error("Invalid length field: {0}")]
     InvalidLengthField(u32),
+    /// The input contains bytes after the end of the message
+    #[error("Trailing bytes: {len} bytes after offset {offset}")]
+    TrailingBytes {
+        /// The offset of the first trailing byte.
+        offset: usize,
+        /// The number of trailing bytes.
+        len: usize,
+    },
+    /// The header's length field disagrees with the length fields of the SD payload
+    #[error("SD length mismatch: header: {header}, payload: {payload}")]
+    SdLengthMismatch {
+        /// The payload length according to the header.
+        header: usize,
+        /// The payload length according to the SD entries and options length fields.
+        payload: usize,
+    },
+    /// The header's length field disagrees with the length of the payload
+    #[error("Payload length mismatch: header: {header}, payload: {payload}")]
+    PayloadLengthMismatch {
+        /// The payload length according to the header.
+        header: usize,
+        /// The actual payload length.
+        payload: usize,
+    },
+    /// A required field of a builder was not set
+    #[error("Uninitialized field: {0}")]
+    UninitializedField(&'static str),
+    /// A builder rejected the values of its fields
+    #[cfg(feature = "alloc")]
+    #[error("Invalid builder fields: {0}")]
+    InvalidBuilderFields(alloc::string::String),
     /// Invalid return code value
     #[error("Unknown return code: {0}")]
     InvalidReturnCode(u8),
     /// Invalid message type value
     #[error("Unknown message type value: {0}")]
     InvalidMessageType(u8),
+    /// The message type does not allow the requested operation, e.g. a response to a notification
+    #[error("Unexpected message type: {0:?}")]
+    UnexpectedMessageType(MessageType),
+    /// The return code does not allow the requested operation, e.g. an error with `Ok`
+    #[error("Unexpected return code: {0:?}")]
+    UnexpectedReturnCode(ReturnCode),
     /// Unknown sd entry value
     #[error("Unknown sd entry value: {0}")]
     UnknownSdEntry(u8),
@@ -36,15 +82,245 @@ pub enum Error {
     /// Invalid ip proto value
     #[error("Unknown ip proto value: {0}")]
     InvalidIpProto(u8),
+    /// A message within a datagram could not be parsed
+    #[cfg(feature = "alloc")]
+    #[error("Invalid message at datagram offset {offset}: {error}")]
+    InvalidDatagram {
+        /// The offset of the invalid message within the datagram
+        offset: usize,
+        /// The error that occurred while parsing the message
+        #[source]
+        error: Box<Error>,
+    },
+    /// A field could not be parsed
+    #[cfg(feature = "alloc")]
+    #[error("Invalid field {path} at offset {offset}: {error}")]
+    Parse {
+        /// The absolute byte offset of the field
+        offset: usize,
+        /// The path of the field, e.g. `sd.options[3].port`
+        path: FieldPath,
+        /// The error that occurred while parsing the field
+        #[source]
+        error: Box<Error>,
+    },
+    /// A buffer is too small to hold the serialized data
+    #[error("Buffer too small: required: {required}, available: {available}")]
+    BufferTooSmall {
+        /// The number of bytes required.
+        required: usize,
+        /// The number of bytes available in the buffer.
+        available: usize,
+    },
+    /// A length does not fit into its length field
+    #[error("Length field overflow: len: {len}, max: {max}")]
+    LengthFieldOverflow {
+        /// The length to be written.
+        len: usize,
+        /// The largest length the length field can hold.
+        max: usize,
+    },
+    /// An encoded string exceeds the size of a fixed length string
+    #[error("String too long: len: {len}, max: {max}")]
+    StringTooLong {
+        /// The size of the encoded string including byte order mark and terminator.
+        len: usize,
+        /// The fixed size of the string.
+        max: usize,
+    },
+    /// An array contains more elements than its maximum
+    #[error("Too many elements: max: {max}, actual: {actual}")]
+    TooManyElements {
+        /// The largest number of elements allowed.
+        max: usize,
+        /// The actual number of elements.
+        actual: usize,
+    },
+    /// An element of a dynamic length array occupies no bytes,
+    /// so the end of the array would never be reached
+    #[error("Zero sized array element")]
+    ZeroSizedElement,
+    /// A TLV data id exceeds 12 bits
+    #[error("Invalid data id: {0}")]
+    InvalidDataId(u16),
+    /// A TLV data id occurs more than once within a struct
+    #[error("Duplicate data id: {0}")]
+    DuplicateDataId(u16),
+    /// A required TLV member is missing
+    #[error("Missing data id: {0}")]
+    MissingDataId(u16),
+    /// The value of a TLV member does not match its wire type
+    #[error("Invalid TLV value of data id {data_id}: {len} bytes")]
+    InvalidTlvValue {
+        /// The data id of the member.
+        data_id: u16,
+        /// The length of the value.
+        len: usize,
+    },
+    /// A TLV member has the static wire type but the codec has no static length field
+    #[error("Missing static length field of data id {0}")]
+    MissingStaticLengthField(u16),
+    /// A union type selector is reserved or does not fit into the type field
+    #[error("Invalid type selector: {0}")]
+    InvalidTypeSelector(u32),
+    /// The type has no representation in the SOME/IP payload encoding
+    #[error("Unsupported type: {0}")]
+    UnsupportedType(&'static str),
+    /// Invalid boolean value
+    #[error("Invalid bool value: {0}")]
+    InvalidBool(u8),
+    /// Invalid string encoding, e.g. a missing byte order mark
+    #[error("Invalid string: {0}")]
+    InvalidString(&'static str),
+    /// A value does not match its data type
+    #[error("Invalid value: expected {0}")]
+    InvalidValue(&'static str),
+    /// There is no data type for the payload of a message
+    #[error("Unknown payload type: service {service_id:#06x}, method {method_id:#06x}, {message_type:?}")]
+    UnknownPayloadType {
+        /// The service id of the message.
+        service_id: ServiceId,
+        /// The method id of the message.
+        method_id: MethodId,
+        /// The message type of the message.
+        message_type: MessageType,
+    },
+    /// A schema is malformed or inconsistent, e.g. refers to an unknown type
+    #[cfg(feature = "alloc")]
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(alloc::string::String),
+    /// Custom error of a serde implementation
+    #[cfg(feature = "alloc")]
+    #[error("{0}")]
+    Custom(alloc::string::String),
     /// Invalid ip proto value
     #[cfg(feature = "url")]
     #[error("Invalid url: {0}")]
     InvalidUrl(&'static str),
 }
 
+impl Error {
+    /// The absolute byte offset of the field that caused the error, if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "alloc")]
+            Error::Parse { offset, .. } => Some(*offset),
+            #[cfg(feature = "alloc")]
+            Error::InvalidDatagram { offset, error } => Some(offset + error.offset().unwrap_or(0)),
+            _ => None,
+        }
+    }
+
+    /// The path of the field that caused the error, if known
+    #[cfg(feature = "alloc")]
+    pub fn path(&self) -> Option<&FieldPath> {
+        match self {
+            Error::Parse { path, .. } => Some(path),
+            Error::InvalidDatagram { error, .. } => error.path(),
+            _ => None,
+        }
+    }
+
+    /// The underlying error without location information
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            #[cfg(feature = "alloc")]
+            Error::Parse { error, .. } | Error::InvalidDatagram { error, .. } => error.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// Adds the location of the field to the error.
+    ///
+    /// The offset is only kept for the innermost field,
+    /// outer fields extend the path.
+    #[cfg(feature = "alloc")]
+    #[cold]
+    pub(crate) fn context(self, offset: usize, segment: PathSegment) -> Error {
+        match self {
+            Error::Parse {
+                offset,
+                mut path,
+                error,
+            } => {
+                path.0.insert(0, segment);
+                Error::Parse {
+                    offset,
+                    path,
+                    error,
+                }
+            }
+            error => Error::Parse {
+                offset,
+                path: FieldPath(vec![segment]),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Without `alloc`, errors carry no location.
+    #[cfg(not(feature = "alloc"))]
+    pub(crate) fn context(self, _offset: usize, _segment: PathSegment) -> Error {
+        self
+    }
+}
+
+/// Adds the location of a field to the error of a result.
+pub(crate) trait Context<T> {
+    fn context(self, offset: usize, segment: PathSegment) -> Result<T, Error>;
+}
+
+impl<T> Context<T> for Result<T, Error> {
+    fn context(self, offset: usize, segment: PathSegment) -> Result<T, Error> {
+        self.map_err(|e| e.context(offset, segment))
+    }
+}
+
+/// A segment of a [`FieldPath`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathSegment {
+    /// Named field of a structure
+    Field(&'static str),
+    /// Member of a structure named at runtime, e.g. by a schema
+    #[cfg(feature = "alloc")]
+    Member(String),
+    /// Index within a sequence
+    Index(usize),
+}
+
+/// Path of a field within a message, e.g. `sd.options[3].port`
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FieldPath(Vec<PathSegment>);
+
+#[cfg(feature = "alloc")]
+impl FieldPath {
+    /// The segments of the path
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if i == 0 => write!(f, "{}", name)?,
+                PathSegment::Field(name) => write!(f, ".{}", name)?,
+                PathSegment::Member(name) if i == 0 => write!(f, "{}", name)?,
+                PathSegment::Member(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Transforms std::io::Error to a Error.
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Error {
-        Error::Io(e)
+        Error::Io(e.kind())
     }
 }
@@ -1,14 +1,49 @@
 //! Crate for parsing the SOME/IP network protocol (without payload interpretation).
 
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Zero-copy support for the bytes crate
+#[cfg(feature = "bytes")]
+mod buf;
+/// Message builder and constructors
+#[cfg(feature = "alloc")]
+mod builder;
+/// Multiple messages within a datagram
+#[cfg(feature = "alloc")]
+mod datagram;
 mod error;
 /// Parse someip messages
 mod parser;
+#[cfg(feature = "alloc")]
+pub mod payload;
+#[cfg(feature = "schema")]
+pub mod schema;
 /// Serialize someip messages
 mod serializer;
 /// Message types
 mod types;
+/// Vectored serialization
+#[cfg(feature = "alloc")]
+mod vectored;
+/// Views of serialized messages
+mod view;
 
-pub use error::Error;
+#[cfg(feature = "bytes")]
+pub use buf::BytesMessage;
+#[cfg(feature = "alloc")]
+pub use builder::MessageBuilder;
+#[cfg(feature = "alloc")]
+pub use datagram::Messages;
+#[cfg(feature = "std")]
+pub use datagram::{DatagramPacker, Flush};
+#[cfg(feature = "alloc")]
+pub use error::FieldPath;
+pub use error::{Error, PathSegment};
 pub use types::*;
+#[cfg(feature = "alloc")]
+pub use vectored::VectoredMessage;
+pub use view::{HeaderMut, HeaderRef};
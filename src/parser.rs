@@ -1,146 +1,245 @@
-use crate::{types::*, Error};
-use byteorder::{BigEndian as BE, ReadBytesExt};
-use std::io::{Cursor, Read};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-
+use crate::{types::*, Error, HeaderRef};
+
+#[cfg(feature = "alloc")]
+use crate::error::{
+    Context,
+    PathSegment::{Field, Index},
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "std")]
+use std::io::Read;
+
+#[cfg(feature = "alloc")]
 impl<'a> Message<'a> {
     /// Parse a message from a byte slice.
     pub fn from_slice(input: &'a [u8]) -> Result<Message<'a>, Error> {
-        match Header::from_slice(input) {
-            Ok(Header {
-                message_id:
-                    MessageId {
-                        service_id: 0xFFFF,
-                        method_id: 0x0000,
-                    },
-                length: 8,
-                request_id:
-                    RequestId {
-                        client_id: 0xDEAD,
-                        session_id: 0xBEEF,
-                    },
-                protocol_version: 0x01,
-                interface_version: 0x01,
-                message_type: MessageType::RequestNoReturn,
-                return_code: ReturnCode::Ok,
-            }) => Ok(Message::CookieClient),
-
-            Ok(Header {
-                message_id:
-                    MessageId {
-                        service_id: 0xFFFF,
-                        method_id: 0x8000,
-                    },
-                length: 8,
-                request_id:
-                    RequestId {
-                        client_id: 0xDEAD,
-                        session_id: 0xBEEF,
-                    },
-                protocol_version: 0x01,
-                interface_version: 0x01,
-                message_type: MessageType::Notification,
-                return_code: ReturnCode::Ok,
-            }) => Ok(Message::CookieServer),
-
-            Ok(header) => {
-                let payload_len = header.payload_len();
-                if input.len() < Header::LENGTH + payload_len {
-                    return Err(Error::NotEnoughData {
-                        min: Header::LENGTH + payload_len,
-                        actual: input.len(),
-                    });
-                }
-
-                let payload = &input[Header::LENGTH..Header::LENGTH + payload_len];
-
-                if header.is_sd() {
-                    let mut buffer = Cursor::new(payload);
-                    Ok(Message::Sd(header, SdPayload::from_reader(&mut buffer)?))
-                } else {
-                    Ok(Message::Rpc(header, payload))
-                }
+        Message::from_slice_with_mode(input, ParseMode::Strict)
+    }
+
+    /// Parse a message from a byte slice using the given parse mode.
+    pub fn from_slice_with_mode(input: &'a [u8], mode: ParseMode) -> Result<Message<'a>, Error> {
+        let header = HeaderRef::from_slice(input)?;
+        if header.is_cookie_client() {
+            return Ok(Message::CookieClient);
+        }
+        if header.is_cookie_server() {
+            return Ok(Message::CookieServer);
+        }
+
+        let header = header.to_header_with_mode(mode)?;
+        let payload_len = header.payload_len();
+        if input.len() < Header::LENGTH + payload_len {
+            return Err(Error::NotEnoughData {
+                min: Header::LENGTH + payload_len,
+                actual: input.len(),
+            });
+        }
+
+        let payload = &input[Header::LENGTH..Header::LENGTH + payload_len];
+
+        if header.is_sd() {
+            Ok(Message::Sd(
+                header,
+                SdPayload::from_slice_at(payload, Header::LENGTH)?,
+            ))
+        } else {
+            Ok(Message::Rpc(header, payload))
+        }
+    }
+
+    /// Parse a message from a byte slice which must contain exactly this message.
+    ///
+    /// In contrast to [`Message::from_slice`], bytes after the end of the message
+    /// are reported as [`Error::TrailingBytes`] and an SD payload whose own length
+    /// fields disagree with the header's length field is reported as
+    /// [`Error::SdLengthMismatch`].
+    pub fn from_slice_exact(input: &'a [u8]) -> Result<Message<'a>, Error> {
+        let header = Header::from_slice(input)?;
+        let message_len = header.message_len();
+        if input.len() < message_len {
+            return Err(Error::NotEnoughData {
+                min: message_len,
+                actual: input.len(),
+            });
+        }
+
+        if header.is_sd() {
+            let sd_len = SdPayload::encoded_len(&input[Header::LENGTH..], Header::LENGTH)
+                .context(Header::LENGTH, Field("sd"))?;
+            if sd_len != header.payload_len() {
+                return Err(Error::SdLengthMismatch {
+                    header: header.payload_len(),
+                    payload: sd_len,
+                });
             }
-            Err(e) => Err(e),
         }
+
+        if input.len() > message_len {
+            return Err(Error::TrailingBytes {
+                offset: message_len,
+                len: input.len() - message_len,
+            });
+        }
+
+        Message::from_slice(input)
     }
 }
 
 impl Header {
     /// Parse a header from a byte slice.
+    #[inline]
     pub fn from_slice(input: &[u8]) -> Result<Header, Error> {
-        if input.len() < Header::LENGTH {
-            return Err(Error::NotEnoughData {
-                min: Header::LENGTH,
-                actual: input.len(),
-            });
-        }
+        Header::from_slice_with_mode(input, ParseMode::Strict)
+    }
 
-        let mut buffer = Cursor::new(input);
-        Header::from_reader(&mut buffer)
+    /// Parse a header from a byte slice using the given parse mode.
+    #[inline]
+    pub fn from_slice_with_mode(input: &[u8], mode: ParseMode) -> Result<Header, Error> {
+        HeaderRef::from_slice(input)?.to_header_with_mode(mode)
+    }
+
+    /// Parse a header from its fixed size byte representation.
+    #[inline]
+    pub fn from_bytes(bytes: &[u8; Header::LENGTH]) -> Result<Header, Error> {
+        HeaderRef::new(bytes).to_header()
+    }
+
+    /// Parse a header from its fixed size byte representation using the given parse mode.
+    #[inline]
+    pub fn from_bytes_with_mode(
+        bytes: &[u8; Header::LENGTH],
+        mode: ParseMode,
+    ) -> Result<Header, Error> {
+        HeaderRef::new(bytes).to_header_with_mode(mode)
     }
 
     /// Parse a header from a `Read`
+    #[cfg(feature = "std")]
     pub fn from_reader<R: Read>(reader: &mut R) -> Result<Header, Error> {
-        let service_id = reader.read_u16::<BE>()?;
-        let method_id = reader.read_u16::<BE>()?;
-        let length = reader.read_u32::<BE>()?;
-        let client_id = reader.read_u16::<BE>()?;
-        let session_id = reader.read_u16::<BE>()?;
-        let protocol_version = reader.read_u8()?;
-        let interface_version = reader.read_u8()?;
-        let message_type = reader.read_u8()?.try_into()?;
-        let return_code = reader.read_u8()?.try_into()?;
-
-        let header = Header {
-            message_id: MessageId {
-                service_id,
-                method_id,
-            },
-            length,
-            request_id: RequestId {
-                client_id,
-                session_id,
-            },
-            protocol_version,
-            interface_version,
-            message_type,
-            return_code,
-        };
-
-        if (header.is_sd() && length < 20) || (!header.is_sd() && length < 8) {
-            return Err(Error::InvalidLengthField(length));
-        }
+        Header::from_reader_with_mode(reader, ParseMode::Strict)
+    }
 
-        Ok(header)
+    /// Parse a header from a `Read` using the given parse mode.
+    #[cfg(feature = "std")]
+    pub fn from_reader_with_mode<R: Read>(
+        reader: &mut R,
+        mode: ParseMode,
+    ) -> Result<Header, Error> {
+        let mut buffer = Vec::with_capacity(Header::LENGTH);
+        reader
+            .take(Header::LENGTH as u64)
+            .read_to_end(&mut buffer)?;
+        Header::from_slice_with_mode(&buffer, mode)
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdPayload {
+    /// Parse SD payload from a byte slice.
+    pub fn from_slice(input: &[u8]) -> Result<SdPayload, Error> {
+        SdPayload::from_slice_at(input, 0)
+    }
+
+    /// Parse SD payload from a byte slice starting at the given offset within a message.
+    fn from_slice_at(input: &[u8], offset: usize) -> Result<SdPayload, Error> {
+        SdPayload::decode(&mut SliceReader::with_offset(input, offset)).context(offset, Field("sd"))
+    }
+
     /// Parse SD payload from a `Read`
+    ///
+    /// If the reader ends early, the error refers to the truncated field.
+    #[cfg(feature = "std")]
     pub fn from_reader<R: Read>(reader: &mut R) -> Result<SdPayload, Error> {
-        let flags = reader.read_u8()?;
-        reader.read_u24::<BE>()?; // reserved
+        fn read<R: Read>(reader: &mut R, buffer: &mut Vec<u8>, len: usize) -> Result<(), Error> {
+            let min = buffer.len() + len;
+            reader.take(len as u64).read_to_end(buffer)?;
+            if buffer.len() < min {
+                return Err(Error::NotEnoughData {
+                    min,
+                    actual: buffer.len(),
+                });
+            }
+            Ok(())
+        }
+
+        fn read_len<R: Read>(reader: &mut R, buffer: &mut Vec<u8>) -> Result<usize, Error> {
+            read(reader, buffer, 4)?;
+            let bytes = &buffer[buffer.len() - 4..];
+            Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        }
+
+        let mut buffer = Vec::new();
+        let mut read_all = || {
+            read(reader, &mut buffer, 4)?; // flags, reserved
+            let entries_len = read_len(reader, &mut buffer)?;
+            read(reader, &mut buffer, entries_len)?;
+            let options_len = read_len(reader, &mut buffer)?;
+            read(reader, &mut buffer, options_len)
+        };
+
+        match read_all() {
+            Ok(()) => SdPayload::from_slice(&buffer),
+            // prefer the error of the truncated field
+            Err(error @ Error::NotEnoughData { .. }) => {
+                SdPayload::from_slice(&buffer).and(Err(error))
+            }
+            Err(error) => Err(error),
+        }
+    }
 
-        let entries_len: usize = reader.read_u32::<BE>()? as usize;
+    /// Length of the SD payload according to its entries and options length fields.
+    fn encoded_len(input: &[u8], offset: usize) -> Result<usize, Error> {
+        let mut reader = SliceReader::with_offset(input, offset);
+        reader.take(4, "flags")?;
+        let entries_len = reader.read_u32("entries_length")? as usize;
+        reader.take(entries_len, "entries")?;
+        let options_len = reader.read_u32("options_length")?;
+
+        (12 + entries_len)
+            .checked_add(options_len as usize)
+            .ok_or(Error::InvalidLengthField(options_len))
+    }
+
+    fn decode(reader: &mut SliceReader) -> Result<SdPayload, Error> {
+        let flags = reader.read_u8("flags")?;
+        reader.read_u24("reserved")?;
+
+        let entries_len = reader.read_u32("entries_length")? as usize;
+        let entries_offset = reader.offset();
+        let mut entries_reader =
+            SliceReader::with_offset(reader.take(entries_len, "entries")?, entries_offset);
         let num_entries = entries_len / SdEntry::LENGTH;
         let mut entries: Vec<SdEntry> = Vec::with_capacity(num_entries);
 
-        for _ in 0..num_entries {
-            entries.push(SdEntry::from_reader(reader)?);
+        for index in 0..num_entries {
+            let offset = entries_reader.offset();
+            entries.push(
+                SdEntry::decode(&mut entries_reader)
+                    .context(offset, Index(index))
+                    .context(entries_offset, Field("entries"))?,
+            );
         }
 
-        let options_len: usize = reader.read_u32::<BE>()? as usize;
+        let options_len = reader.read_u32("options_length")? as usize;
+        let options_offset = reader.offset();
         let mut options: Vec<SdOption> = Vec::new();
 
         let mut read_len: usize = 0;
+        let mut index = 0;
         while read_len < options_len {
-            let (option_len, option) = SdOption::from_reader(reader)?;
+            let offset = reader.offset();
+            let (option_len, option) = SdOption::decode(reader)
+                .context(offset, Index(index))
+                .context(options_offset, Field("options"))?;
             if let Some(value) = option {
                 options.push(value);
             }
 
             read_len += option_len;
+            index += 1;
         }
 
         Ok(SdPayload {
@@ -151,18 +250,20 @@ impl SdPayload {
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdEntry {
-    fn from_reader<R: Read>(reader: &mut R) -> Result<SdEntry, Error> {
-        let entry_type = reader.read_u8()?;
-        let index1 = reader.read_u8()?;
-        let index2 = reader.read_u8()?;
-        let num = reader.read_u8()?;
+    fn decode(reader: &mut SliceReader) -> Result<SdEntry, Error> {
+        let type_offset = reader.offset();
+        let entry_type = reader.read_u8("type")?;
+        let index1 = reader.read_u8("index1")?;
+        let index2 = reader.read_u8("index2")?;
+        let num = reader.read_u8("num")?;
         let num1 = (num >> 4) & 0x0F;
         let num2 = num & 0x0F;
-        let service_id = reader.read_u16::<BE>()?;
-        let instance_id = reader.read_u16::<BE>()?;
-        let major_version = reader.read_u8()?;
-        let ttl = reader.read_u24::<BE>()?;
+        let service_id = reader.read_u16("service_id")?;
+        let instance_id = reader.read_u16("instance_id")?;
+        let major_version = reader.read_u8("major_version")?;
+        let ttl = reader.read_u24("ttl")?;
 
         let options = SdOptionRef {
             index1,
@@ -172,9 +273,9 @@ impl SdEntry {
         };
 
         if SdEntry::is_service(entry_type) {
-            let minor_version = reader.read_u32::<BE>()?;
+            let minor_version = reader.read_u32("minor_version")?;
 
-            Ok(SdEntry::try_from((
+            SdEntry::try_from((
                 entry_type,
                 SdServiceEntry {
                     service_id,
@@ -184,12 +285,13 @@ impl SdEntry {
                     ttl,
                     options,
                 },
-            ))?)
+            ))
+            .context(type_offset, Field("type"))
         } else {
-            reader.read_u16::<BE>()?; // reserved
-            let eventgroup_id = reader.read_u16::<BE>()?;
+            reader.read_u16("reserved")?;
+            let eventgroup_id = reader.read_u16("eventgroup_id")?;
 
-            Ok(SdEntry::try_from((
+            SdEntry::try_from((
                 entry_type,
                 SdEventgroupEntry {
                     service_id,
@@ -199,47 +301,113 @@ impl SdEntry {
                     ttl,
                     options,
                 },
-            ))?)
+            ))
+            .context(type_offset, Field("type"))
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdOption {
-    fn from_reader<R: Read>(reader: &mut R) -> Result<(usize, Option<SdOption>), Error> {
-        let option_len: usize = reader.read_u16::<BE>()? as usize;
-        let option_type = reader.read_u8()?;
+    fn decode(reader: &mut SliceReader) -> Result<(usize, Option<SdOption>), Error> {
+        let option_len: usize = reader.read_u16("length")? as usize;
+        let type_offset = reader.offset();
+        let option_type = reader.read_u8("type")?;
 
         if !SdOption::is_ip_option(option_type) {
-            let mut buffer: Vec<u8> = vec![0; option_len];
-            reader.read_exact(&mut buffer)?;
+            reader.take(option_len, "data")?;
 
             return Ok((3 + option_len, None)); // drop option
         }
 
-        reader.read_u8()?; // reserved
+        reader.read_u8("reserved")?;
 
         let ip = if SdOption::is_ip4_option(option_type) {
             let mut buffer = [0u8; 4];
-            reader.read_exact(&mut buffer)?;
+            buffer.copy_from_slice(reader.take(4, "address")?);
             IpAddr::V4(Ipv4Addr::from(buffer))
         } else {
             let mut buffer = [0u8; 16];
-            reader.read_exact(&mut buffer)?;
+            buffer.copy_from_slice(reader.take(16, "address")?);
             IpAddr::V6(Ipv6Addr::from(buffer))
         };
 
-        reader.read_u8()?; // reserved
-        let proto = IpProto::try_from(reader.read_u8()?)?;
-        let port = reader.read_u16::<BE>()?;
+        reader.read_u8("reserved")?;
+        let proto_offset = reader.offset();
+        let proto =
+            IpProto::try_from(reader.read_u8("proto")?).context(proto_offset, Field("proto"))?;
+        let port = reader.read_u16("port")?;
 
-        let option = SdOption::from(option_type, SdEndpointOption { ip, port, proto })?;
+        let option = SdOption::from(option_type, SdEndpointOption { ip, port, proto })
+            .context(type_offset, Field("type"))?;
         Ok((option.len(), Some(option)))
     }
 }
 
+/// Reads big endian values from a byte slice.
+///
+/// Errors refer to the named field and its absolute offset.
+#[cfg(feature = "alloc")]
+struct SliceReader<'a> {
+    input: &'a [u8],
+    base: usize,
+    pos: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> SliceReader<'a> {
+    /// Creates a reader for input which starts at `base` within a message.
+    fn with_offset(input: &'a [u8], base: usize) -> Self {
+        Self {
+            input,
+            base,
+            pos: 0,
+        }
+    }
+
+    /// The absolute offset of the next byte
+    fn offset(&self) -> usize {
+        self.base + self.pos
+    }
+
+    fn take(&mut self, len: usize, field: &'static str) -> Result<&'a [u8], Error> {
+        if self.input.len() - self.pos < len {
+            return Err(Error::NotEnoughData {
+                min: self.pos + len,
+                actual: self.input.len(),
+            })
+            .context(self.offset(), Field(field));
+        }
+
+        let bytes = &self.input[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self, field: &'static str) -> Result<u8, Error> {
+        Ok(self.take(1, field)?[0])
+    }
+
+    fn read_u16(&mut self, field: &'static str) -> Result<u16, Error> {
+        let bytes = self.take(2, field)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u24(&mut self, field: &'static str) -> Result<u32, Error> {
+        let bytes = self.take(3, field)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    fn read_u32(&mut self, field: &'static str) -> Result<u32, Error> {
+        let bytes = self.take(4, field)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::PathSegment;
     use std::str::FromStr;
 
     #[test]
@@ -615,4 +783,227 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn parse_sd_payload_from_reader() {
+        let payload: &[u8] = &[
+            0x80, 0x00, 0x00, 0x00, // sdFlags(08), reserved(u24)
+            // entries
+            0x00, 0x00, 0x00, 0x10, // entries-length(u32)
+            // find-service
+            0x00, 0x00, 0x00, 0x10, // entryType(u8), index1(u8), index2,(u8) num1|2(u8)
+            0x01, 0x03, 0xFF, 0xFF, // serviceId(u16), instanceId(u16)
+            0xFF, 0x00, 0x00, 0x03, // majorVersion(u8), ttl(u24)
+            0xFF, 0xFF, 0xFF, 0xFF, // minorVersion(u32)
+            // options
+            0x00, 0x00, 0x00, 0x0C, // options-length(u32)
+            // ip-4 endpoint
+            0x00, 0x09, 0x04, 0x00, // length(u16), optionType(u8), reserved(u8)
+            0x7F, 0x00, 0x00, 0x01, // ip4(u32)
+            0x00, 0x11, 0x75, 0x30, // reserved(u8), proto(u8), port(u16)
+            // next message
+            0x01, 0x02, 0x03, 0x04,
+        ];
+
+        let mut reader = std::io::Cursor::new(payload);
+        let sd = SdPayload::from_reader(&mut reader).unwrap();
+        assert_eq!(sd, SdPayload::from_slice(payload).unwrap());
+        assert_eq!(1, sd.entries.len());
+        assert_eq!(1, sd.options.len());
+        assert_eq!(40, reader.position());
+
+        let mut reader = std::io::Cursor::new(&payload[..30]);
+        let error = SdPayload::from_reader(&mut reader).unwrap_err();
+        assert_eq!(Some(30), error.offset());
+        assert_eq!("sd.options[0].type", error.path().unwrap().to_string());
+        assert_eq!(
+            &Error::NotEnoughData {
+                min: 31,
+                actual: 30
+            },
+            error.root_cause()
+        );
+    }
+
+    #[test]
+    fn parse_unknown_values_lenient() {
+        let message: &[u8] = &[
+            0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+            0x00, 0x00, 0x00, 0x09, // length(u32)
+            0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+            0x01, 0x01, 0x40, 0x60, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+            0xAA, // payload
+        ];
+
+        let error = Message::from_slice(message).unwrap_err();
+        assert_eq!(&Error::InvalidMessageType(0x40), error.root_cause());
+        assert_eq!(
+            Err(error),
+            Header::from_slice_with_mode(message, ParseMode::Strict)
+        );
+
+        let header = Header::from_slice_with_mode(message, ParseMode::Lenient).unwrap();
+        assert_eq!(MessageType::RequestAck, header.message_type);
+        assert_eq!(ReturnCode::Unknown(0x60), header.return_code);
+        assert_eq!(&message[..16], header.to_vec());
+
+        let message = [&message[..14], &[0x13, 0xFF, 0xAA]].concat();
+        assert_eq!(
+            Message::from_slice_with_mode(&message, ParseMode::Lenient).unwrap(),
+            Message::Rpc(
+                Header {
+                    message_type: MessageType::Unknown(0x13),
+                    return_code: ReturnCode::Unknown(0xFF),
+                    ..header
+                },
+                &[0xAA],
+            )
+        );
+    }
+
+    #[test]
+    fn parse_exact_trailing_bytes() {
+        let message: &[u8] = &[
+            0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+            0x00, 0x00, 0x00, 0x09, // length(u32)
+            0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+            0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+            0xAA, // payload
+            0xBB, 0xCC, // trailing
+        ];
+
+        assert!(Message::from_slice(message).is_ok());
+        assert!(Message::from_slice_exact(&message[..17]).is_ok());
+        assert_eq!(
+            Err(Error::TrailingBytes { offset: 17, len: 2 }),
+            Message::from_slice_exact(message)
+        );
+        assert_eq!(
+            Err(Error::NotEnoughData {
+                min: 17,
+                actual: 16
+            }),
+            Message::from_slice_exact(&message[..16])
+        );
+    }
+
+    #[test]
+    fn parse_exact_sd_length_mismatch() {
+        let header: &[u8] = &[
+            0xFF, 0xFF, 0x81, 0x00, // serviceId(u16), methodId(u16)
+            0x00, 0x00, 0x00, 0x14, // length(u32)
+            0x00, 0x00, 0x00, 0x00, // clientId(u16), sessionId(u16)
+            0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        ];
+        let payload: &[u8] = &[
+            0xC0, 0x00, 0x00, 0x00, // sdFlags(08), reserved(u24)
+            0x00, 0x00, 0x00, 0x00, // entries-length(u32)
+            0x00, 0x00, 0x00, 0x04, // options-length(u32)
+            0x00, 0x01, 0x01, 0x00, // length(u16), optionType(u8), reserved(u8)
+        ];
+        let message: &[u8] = &[header, payload].concat();
+
+        // the header's length field misses the option
+        assert!(matches!(
+            Message::from_slice(message).unwrap_err().root_cause(),
+            Error::NotEnoughData { .. }
+        ));
+        assert_eq!(
+            Err(Error::SdLengthMismatch {
+                header: 12,
+                payload: 16
+            }),
+            Message::from_slice_exact(message)
+        );
+
+        let mut message = message.to_vec();
+        message[7] = 0x18;
+        assert!(matches!(
+            Message::from_slice_exact(&message),
+            Ok(Message::Sd(_, _))
+        ));
+        // an options length field exceeding the address space
+        message[24..28].copy_from_slice(&[0xFF; 4]);
+        let error = Message::from_slice_exact(&message).unwrap_err();
+        if cfg!(target_pointer_width = "64") {
+            assert!(matches!(error, Error::SdLengthMismatch { .. }));
+        } else {
+            assert_eq!(&Error::InvalidLengthField(0xFFFF_FFFF), error.root_cause());
+        }
+    }
+
+    #[test]
+    fn parse_error_location() {
+        let header: &[u8] = &[
+            0xFF, 0xFF, 0x81, 0x00, // serviceId(u16), methodId(u16)
+            0x00, 0x00, 0x00, 0x3C, // length(u32)
+            0x00, 0x00, 0x00, 0x00, // clientId(u16), sessionId(u16)
+            0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        ];
+        let payload: &[u8] = &[
+            0xC0, 0x00, 0x00, 0x00, // sdFlags(08), reserved(u24)
+            // entries
+            0x00, 0x00, 0x00, 0x10, // entries-length(u32)
+            // offer-service
+            0x01, 0x00, 0x00, 0x20, // entryType(u8), index1(u8), index2,(u8) num1|2(u8)
+            0x01, 0x03, 0x00, 0x01, // serviceId(u16), instanceId(u16)
+            0x02, 0x00, 0x00, 0x03, // majorVersion(u8), ttl(u24)
+            0x00, 0x00, 0x00, 0x0A, // minorVersion(u32)
+            // options
+            0x00, 0x00, 0x00, 0x18, // options-length(u32)
+            // ip-4 endpoint
+            0x00, 0x09, 0x04, 0x00, // length(u16), optionType(u8), reserved(u8)
+            0x7F, 0x00, 0x00, 0x01, // ip4(u32)
+            0x00, 0x11, 0x75, 0x30, // reserved(u8), proto(u8), port(u16)
+            // ip-4 endpoint with invalid proto
+            0x00, 0x09, 0x04, 0x00, // length(u16), optionType(u8), reserved(u8)
+            0x7F, 0x00, 0x00, 0x01, // ip4(u32)
+            0x00, 0x07, 0x75, 0x30, // reserved(u8), proto(u8), port(u16)
+        ];
+        let message: &[u8] = &[header, payload].concat();
+
+        let error = Message::from_slice(message).unwrap_err();
+        assert_eq!(Some(65), error.offset());
+        assert_eq!("sd.options[1].proto", error.path().unwrap().to_string());
+        assert_eq!(
+            &[
+                PathSegment::Field("sd"),
+                PathSegment::Field("options"),
+                PathSegment::Index(1),
+                PathSegment::Field("proto"),
+            ],
+            error.path().unwrap().segments()
+        );
+        assert_eq!(&Error::InvalidIpProto(0x07), error.root_cause());
+        assert_eq!(
+            "Invalid field sd.options[1].proto at offset 65: Unknown ip proto value: 7",
+            error.to_string()
+        );
+
+        let error = SdPayload::from_slice(&payload[..20]).unwrap_err();
+        assert_eq!(Some(8), error.offset());
+        assert_eq!("sd.entries", error.path().unwrap().to_string());
+
+        let mut header = header.to_vec();
+        header[7] = 0x04;
+        let error = Header::from_reader(&mut std::io::Cursor::new(header)).unwrap_err();
+        assert_eq!(Some(4), error.offset());
+        assert_eq!("header.length", error.path().unwrap().to_string());
+        assert_eq!(&Error::InvalidLengthField(4), error.root_cause());
+    }
+
+    #[test]
+    fn parse_from_failing_reader() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::ConnectionReset.into())
+            }
+        }
+
+        let error = Header::from_reader(&mut FailingReader).unwrap_err();
+        assert_eq!(Error::Io(std::io::ErrorKind::ConnectionReset), error);
+        assert_eq!(Err(error), SdPayload::from_reader(&mut FailingReader));
+    }
 }
@@ -0,0 +1,306 @@
+use super::{ByteOrder, LengthField};
+use crate::{error::PathSegment::Index, Error};
+use alloc::vec::Vec;
+
+/// Encoder and decoder of SOME/IP arrays.
+///
+/// The length field holds the size of the elements in bytes. Arrays without
+/// a length field extend to `max_elements` or to the end of the input.
+/// Multidimensional arrays nest one codec per dimension, each with its own
+/// length field, by encoding and decoding the elements with the inner codec.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ArrayCodec {
+    /// Length field preceding the elements
+    pub length_field: LengthField,
+    /// Smallest number of elements
+    pub min_elements: usize,
+    /// Largest number of elements
+    pub max_elements: Option<usize>,
+    /// Byte order of the length field
+    pub byte_order: ByteOrder,
+}
+
+impl ArrayCodec {
+    /// Codec of fixed length arrays of `len` elements.
+    pub fn fixed(len: usize) -> Self {
+        Self {
+            length_field: LengthField::None,
+            min_elements: len,
+            max_elements: Some(len),
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Codec of dynamic length arrays preceded by a length field.
+    pub fn dynamic(length_field: LengthField) -> Self {
+        Self {
+            length_field,
+            min_elements: 0,
+            max_elements: None,
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Checks the number of elements against the minimum and maximum.
+    ///
+    /// Too few elements are reported as [`Error::NotEnoughData`] in elements.
+    pub fn check_len(&self, len: usize) -> Result<(), Error> {
+        if len < self.min_elements {
+            return Err(Error::NotEnoughData {
+                min: self.min_elements,
+                actual: len,
+            });
+        }
+        match self.max_elements {
+            Some(max) if len > max => Err(Error::TooManyElements { max, actual: len }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Appends the encoded array, encoding each element with `encode`.
+    ///
+    /// Elements of dynamic length arrays must not be encoded without any bytes.
+    /// On error, the output is left unchanged.
+    pub fn encode<T>(
+        &self,
+        elements: &[T],
+        output: &mut Vec<u8>,
+        encode: impl FnMut(&T, &mut Vec<u8>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let position = output.len();
+        let result = self.encode_at(elements, output, encode);
+        if result.is_err() {
+            output.truncate(position);
+        }
+        result
+    }
+
+    fn encode_at<T>(
+        &self,
+        elements: &[T],
+        output: &mut Vec<u8>,
+        mut encode: impl FnMut(&T, &mut Vec<u8>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.check_len(elements.len())?;
+
+        let position = self.length_field.reserve(output);
+        for (index, element) in elements.iter().enumerate() {
+            let offset = output.len();
+            encode(element, output)?;
+            // an element without bytes could not be decoded again
+            if output.len() == offset && self.fixed_len().is_none() {
+                return Err(Error::ZeroSizedElement.context(offset, Index(index)));
+            }
+        }
+        self.length_field.finish(output, position, self.byte_order)
+    }
+
+    /// Decodes an array from the start of the input.
+    ///
+    /// `decode` reads an element from the start of its input and returns it with
+    /// the number of bytes read. Returns the elements and the number of bytes read,
+    /// including the length field. Errors of elements carry their index. Elements
+    /// without bytes are rejected unless the number of elements is fixed.
+    pub fn decode<'a, T>(
+        &self,
+        input: &'a [u8],
+        mut decode: impl FnMut(&'a [u8]) -> Result<(T, usize), Error>,
+    ) -> Result<(Vec<T>, usize), Error> {
+        let end = self.length_field.delimit(input, self.byte_order)?.end;
+
+        let mut elements = Vec::new();
+        let mut offset = self.length_field.size();
+        while offset < end && Some(elements.len()) != self.fixed_len() {
+            let (element, len) = decode(&input[offset..end])
+                .map_err(|e| e.context(offset, Index(elements.len())))?;
+            if len == 0 && self.fixed_len().is_none() {
+                return Err(Error::ZeroSizedElement.context(offset, Index(elements.len())));
+            }
+            elements.push(element);
+            offset += len;
+        }
+        self.check_len(elements.len())?;
+
+        Ok((elements, offset))
+    }
+
+    /// Number of elements of arrays without a length field and a maximum
+    pub(crate) fn fixed_len(&self) -> Option<usize> {
+        match self.length_field {
+            LengthField::None => self.max_elements,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode_u16(value: &u16, output: &mut Vec<u8>) -> Result<(), Error> {
+        output.extend_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode_u16(input: &[u8]) -> Result<(u16, usize), Error> {
+        match input {
+            [a, b, ..] => Ok((u16::from_be_bytes([*a, *b]), 2)),
+            _ => Err(Error::NotEnoughData {
+                min: 2,
+                actual: input.len(),
+            }),
+        }
+    }
+
+    #[test]
+    fn dynamic_array() {
+        for (length_field, expected) in [
+            (LengthField::None, &[0x00, 0x01, 0x00, 0x02][..]),
+            (LengthField::U8, &[0x04, 0x00, 0x01, 0x00, 0x02][..]),
+            (LengthField::U16, &[0x00, 0x04, 0x00, 0x01, 0x00, 0x02][..]),
+            (
+                LengthField::U32,
+                &[0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02][..],
+            ),
+        ] {
+            let codec = ArrayCodec::dynamic(length_field);
+            let mut output = Vec::new();
+            codec.encode(&[1, 2], &mut output, encode_u16).unwrap();
+            assert_eq!(expected, output);
+            assert_eq!(
+                (vec![1, 2], expected.len()),
+                codec.decode(expected, decode_u16).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_array() {
+        let codec = ArrayCodec::fixed(2);
+        let input = [0x00, 0x01, 0x00, 0x02, 0xFF];
+        assert_eq!((vec![1, 2], 4), codec.decode(&input, decode_u16).unwrap());
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 2, actual: 1 }),
+            codec.decode(&input[..2], decode_u16)
+        );
+    }
+
+    #[test]
+    fn multidimensional_array() {
+        let outer = ArrayCodec::dynamic(LengthField::U8);
+        let inner = ArrayCodec::dynamic(LengthField::U16);
+        let matrix = [vec![1], vec![2, 3]];
+        let expected: &[u8] = &[
+            0x0A, // length(u8)
+            0x00, 0x02, // length(u16)
+            0x00, 0x01, // [0][0]
+            0x00, 0x04, // length(u16)
+            0x00, 0x02, 0x00, 0x03, // [1][0], [1][1]
+        ];
+
+        let mut output = Vec::new();
+        outer
+            .encode(&matrix, &mut output, |row, output| {
+                inner.encode(row, output, encode_u16)
+            })
+            .unwrap();
+        assert_eq!(expected, output);
+
+        let (decoded, len) = outer
+            .decode(expected, |input| inner.decode(input, decode_u16))
+            .unwrap();
+        assert_eq!(matrix.to_vec(), decoded);
+        assert_eq!(expected.len(), len);
+
+        let truncated = [0x08, 0x00, 0x02, 0x00, 0x01, 0x00, 0x04, 0x00, 0x02];
+        let error = outer
+            .decode(&truncated, |input| inner.decode(input, decode_u16))
+            .unwrap_err();
+        assert_eq!(
+            &Error::NotEnoughData { min: 6, actual: 4 },
+            error.root_cause()
+        );
+        assert_eq!(Some(5), error.offset());
+        assert_eq!("[1]", error.path().unwrap().to_string());
+    }
+
+    #[test]
+    fn element_count_limits() {
+        let codec = ArrayCodec {
+            min_elements: 2,
+            max_elements: Some(3),
+            ..ArrayCodec::dynamic(LengthField::U8)
+        };
+
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 2, actual: 1 }),
+            codec.decode(&[0x02, 0x00, 0x01], decode_u16)
+        );
+        assert_eq!(
+            Err(Error::TooManyElements { max: 3, actual: 4 }),
+            codec.decode(
+                &[0x08, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04],
+                decode_u16
+            )
+        );
+        assert_eq!(
+            Err(Error::TooManyElements { max: 3, actual: 4 }),
+            codec.encode(&[1, 2, 3, 4], &mut Vec::new(), encode_u16)
+        );
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 2, actual: 0 }),
+            codec.encode(&[], &mut Vec::new(), encode_u16)
+        );
+    }
+
+    #[test]
+    fn output_unchanged_on_error() {
+        let codec = ArrayCodec::dynamic(LengthField::U8);
+        let mut output = vec![0xAA];
+        let result = codec.encode(&[1, 2, 3], &mut output, |value, output| match value {
+            3 => Err(Error::InvalidValue("element")),
+            value => encode_u16(value, output),
+        });
+        assert_eq!(Err(Error::InvalidValue("element")), result);
+        assert_eq!(vec![0xAA], output);
+
+        let codec = ArrayCodec {
+            max_elements: Some(1),
+            ..codec
+        };
+        assert!(codec.encode(&[1, 2], &mut output, encode_u16).is_err());
+        assert_eq!(vec![0xAA], output);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let decode_unit = |_: &[u8]| Ok(((), 0));
+
+        let error = ArrayCodec::dynamic(LengthField::U8)
+            .decode(&[0x01, 0x00], decode_unit)
+            .unwrap_err();
+        assert_eq!(Some(1), error.offset());
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+        assert!(ArrayCodec::dynamic(LengthField::None)
+            .decode(&[0x00], decode_unit)
+            .is_err());
+
+        assert_eq!(
+            Ok((vec![(), ()], 0)),
+            ArrayCodec::fixed(2).decode(&[0x00], decode_unit)
+        );
+
+        let encode_unit = |_: &(), _: &mut Vec<u8>| Ok(());
+        let mut output = vec![0xAA];
+        let error = ArrayCodec::dynamic(LengthField::U8)
+            .encode(&[()], &mut output, encode_unit)
+            .unwrap_err();
+        assert_eq!(Some(2), error.offset());
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+        assert_eq!(vec![0xAA], output);
+        assert_eq!(
+            Ok(()),
+            ArrayCodec::fixed(2).encode(&[(), ()], &mut output, encode_unit)
+        );
+    }
+}
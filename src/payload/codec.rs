@@ -0,0 +1,560 @@
+use super::{ArrayCodec, ByteOrder, Encoding, LengthField, StringCodec, WireType};
+use crate::{Error, RpcPayload};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// Encoding options of a field, set with `#[someip(...)]` attributes.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct FieldOptions {
+    /// Length field, `None` for the default of the type
+    pub length_field: Option<LengthField>,
+    /// Character encoding of strings
+    pub encoding: Encoding,
+    /// Size of fixed length strings in bytes
+    pub fixed_size: Option<usize>,
+    /// Smallest number of array elements
+    pub min_elements: usize,
+    /// Largest number of array elements
+    pub max_elements: Option<usize>,
+    /// Byte order of numbers and of length and type fields, inherited by members
+    pub byte_order: ByteOrder,
+    /// Alignment in bytes of members following variable size members, inherited by members.
+    ///
+    /// Strings, dynamic length arrays, unions and structs with a length field are
+    /// padded with zeros to a multiple of `alignment` bytes from the start of the payload.
+    pub alignment: usize,
+    /// Offset of the input in the payload, set by the derive macros to find the padding
+    #[doc(hidden)]
+    pub offset: __private::Offset,
+}
+
+impl FieldOptions {
+    fn string_codec(&self) -> StringCodec {
+        let codec = match self.fixed_size {
+            Some(size) => StringCodec::fixed(self.encoding, size),
+            None => {
+                StringCodec::dynamic(self.encoding, self.length_field.unwrap_or(LengthField::U32))
+            }
+        };
+        StringCodec {
+            byte_order: self.byte_order,
+            ..codec
+        }
+    }
+
+    fn array_codec(&self, default: ArrayCodec) -> ArrayCodec {
+        ArrayCodec {
+            length_field: self.length_field.unwrap_or(default.length_field),
+            min_elements: self.min_elements.max(default.min_elements),
+            max_elements: self.max_elements.or(default.max_elements),
+            byte_order: self.byte_order,
+        }
+    }
+
+    /// Options of a member at `offset` of the input, inheriting byte order and alignment.
+    fn member(&self, offset: usize) -> FieldOptions {
+        FieldOptions {
+            byte_order: self.byte_order,
+            alignment: self.alignment,
+            offset: __private::Offset(self.offset.0 + offset),
+            ..FieldOptions::default()
+        }
+    }
+
+    /// Appends the padding after a variable size member.
+    fn pad(&self, output: &mut Vec<u8>) {
+        let len = output.len().next_multiple_of(self.alignment.max(1));
+        output.resize(len, 0x00);
+    }
+
+    /// Number of bytes read of a variable size member of `len` bytes including its padding.
+    ///
+    /// Padding missing at the end of the input is tolerated.
+    fn padded_len(&self, len: usize, input: &[u8]) -> usize {
+        let offset = self.offset.0;
+        let end = (offset + len).next_multiple_of(self.alignment.max(1));
+        (end - offset).min(input.len())
+    }
+
+    /// Decodes the elements of an array, each with the options of its offset.
+    fn decode_elements<'a, T: SomeIpDeserialize<'a>>(
+        &self,
+        codec: ArrayCodec,
+        input: &'a [u8],
+    ) -> Result<(Vec<T>, usize), Error> {
+        codec.decode(input, |element| {
+            let offset = element.as_ptr() as usize - input.as_ptr() as usize;
+            T::decode(&self.member(offset), element)
+        })
+    }
+}
+
+/// Type encoded into SOME/IP payloads, usually derived with `#[derive(SomeIpSerialize)]`.
+pub trait SomeIpSerialize {
+    /// Wire type as member of a TLV encoded struct
+    const WIRE_TYPE: WireType = WireType::Dynamic32;
+
+    /// Appends the encoded value.
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// Encodes the value as payload of a `Message::Rpc`.
+    fn to_payload(&self) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        self.encode(&FieldOptions::default(), &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Type decoded from SOME/IP payloads, usually derived with `#[derive(SomeIpDeserialize)]`.
+pub trait SomeIpDeserialize<'a>: Sized {
+    /// Decodes a value from the start of the input.
+    ///
+    /// Returns the value and the number of bytes read.
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error>;
+
+    /// Decodes the value from the payload of a `Message::Rpc`.
+    ///
+    /// Bytes after the end of the value are reported as [`Error::TrailingBytes`].
+    fn from_payload(payload: RpcPayload<'a>) -> Result<Self, Error> {
+        let (value, len) = Self::decode(&FieldOptions::default(), payload)?;
+        if len < payload.len() {
+            return Err(Error::TrailingBytes {
+                offset: len,
+                len: payload.len() - len,
+            });
+        }
+        Ok(value)
+    }
+}
+
+macro_rules! impl_number {
+    ($($ty:ty => $wire_type:ident),*) => {
+        $(
+            impl SomeIpSerialize for $ty {
+                const WIRE_TYPE: WireType = WireType::$wire_type;
+
+                fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+                    match options.byte_order {
+                        ByteOrder::BigEndian => output.extend_from_slice(&self.to_be_bytes()),
+                        ByteOrder::LittleEndian => output.extend_from_slice(&self.to_le_bytes()),
+                    }
+                    Ok(())
+                }
+            }
+
+            impl<'a> SomeIpDeserialize<'a> for $ty {
+                fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+                    const SIZE: usize = core::mem::size_of::<$ty>();
+                    let Some(bytes) = input.first_chunk::<SIZE>() else {
+                        return Err(Error::NotEnoughData {
+                            min: SIZE,
+                            actual: input.len(),
+                        });
+                    };
+                    let value = match options.byte_order {
+                        ByteOrder::BigEndian => <$ty>::from_be_bytes(*bytes),
+                        ByteOrder::LittleEndian => <$ty>::from_le_bytes(*bytes),
+                    };
+                    Ok((value, SIZE))
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(
+    u8 => Fixed8, u16 => Fixed16, u32 => Fixed32, u64 => Fixed64,
+    i8 => Fixed8, i16 => Fixed16, i32 => Fixed32, i64 => Fixed64,
+    f32 => Fixed32, f64 => Fixed64
+);
+
+impl SomeIpSerialize for bool {
+    const WIRE_TYPE: WireType = WireType::Fixed8;
+
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        (*self as u8).encode(options, output)
+    }
+}
+
+impl<'a> SomeIpDeserialize<'a> for bool {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        match u8::decode(options, input)? {
+            (0, len) => Ok((false, len)),
+            (1, len) => Ok((true, len)),
+            (value, _) => Err(Error::InvalidBool(value)),
+        }
+    }
+}
+
+impl<T: SomeIpSerialize + ?Sized> SomeIpSerialize for &T {
+    const WIRE_TYPE: WireType = T::WIRE_TYPE;
+
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        (**self).encode(options, output)
+    }
+}
+
+impl SomeIpSerialize for str {
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        options.string_codec().encode(self, output)?;
+        options.pad(output);
+        Ok(())
+    }
+}
+
+impl SomeIpSerialize for String {
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        self.as_str().encode(options, output)
+    }
+}
+
+impl<'a> SomeIpDeserialize<'a> for String {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        let (value, len) = options.string_codec().decode(input)?;
+        Ok((value.into_owned(), options.padded_len(len, input)))
+    }
+}
+
+impl<'a> SomeIpDeserialize<'a> for &'a str {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        match options.string_codec().decode(input)? {
+            (Cow::Borrowed(value), len) => Ok((value, options.padded_len(len, input))),
+            (Cow::Owned(_), _) => Err(Error::InvalidString("borrowed strings must be UTF-8")),
+        }
+    }
+}
+
+impl<T: SomeIpSerialize> SomeIpSerialize for [T] {
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        options
+            .array_codec(ArrayCodec::dynamic(LengthField::U32))
+            .encode(self, output, |element, output| {
+                element.encode(&options.member(0), output)
+            })?;
+        options.pad(output);
+        Ok(())
+    }
+}
+
+impl<T: SomeIpSerialize> SomeIpSerialize for Vec<T> {
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        self.as_slice().encode(options, output)
+    }
+}
+
+impl<'a, T: SomeIpDeserialize<'a>> SomeIpDeserialize<'a> for Vec<T> {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        let codec = options.array_codec(ArrayCodec::dynamic(LengthField::U32));
+        let (elements, len) = options.decode_elements(codec, input)?;
+        Ok((elements, options.padded_len(len, input)))
+    }
+}
+
+impl<'a> SomeIpDeserialize<'a> for &'a [u8] {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        let codec = options.array_codec(ArrayCodec::dynamic(LengthField::U32));
+        let range = codec.length_field.delimit(input, codec.byte_order)?;
+        codec.check_len(range.len())?;
+        Ok((&input[range.clone()], options.padded_len(range.end, input)))
+    }
+}
+
+impl<T: SomeIpSerialize, const N: usize> SomeIpSerialize for [T; N] {
+    fn encode(&self, options: &FieldOptions, output: &mut Vec<u8>) -> Result<(), Error> {
+        options
+            .array_codec(ArrayCodec::fixed(N))
+            .encode(self, output, |element, output| {
+                element.encode(&options.member(0), output)
+            })
+    }
+}
+
+impl<'a, T: SomeIpDeserialize<'a>, const N: usize> SomeIpDeserialize<'a> for [T; N] {
+    fn decode(options: &FieldOptions, input: &'a [u8]) -> Result<(Self, usize), Error> {
+        let (elements, len) =
+            options.decode_elements(options.array_codec(ArrayCodec::fixed(N)), input)?;
+        // the options may allow a different number of elements
+        let actual = elements.len();
+        match elements.try_into() {
+            Ok(elements) => Ok((elements, len)),
+            Err(_) if actual > N => Err(Error::TooManyElements { max: N, actual }),
+            Err(_) => Err(Error::NotEnoughData { min: N, actual }),
+        }
+    }
+}
+
+/// Support of the derive macros, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use super::{FieldOptions, SomeIpDeserialize, SomeIpSerialize};
+    use crate::{
+        payload::{LengthField, TlvCodec, TlvMember, UnionCodec, WireType},
+        Error, PathSegment,
+    };
+    pub use alloc::vec::Vec;
+    use core::ops::Range;
+
+    /// Offset of the input in the payload, only set by [`member`]
+    #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+    pub struct Offset(pub(super) usize);
+
+    /// Options of a member at `offset` of the input, inheriting byte order and alignment.
+    pub fn member(options: &FieldOptions, offset: usize) -> FieldOptions {
+        options.member(offset)
+    }
+
+    /// Adds the location of a field to the error.
+    pub fn context(error: Error, offset: usize, segment: PathSegment) -> Error {
+        error.context(offset, segment)
+    }
+
+    /// Range of the members of a struct delimited by the length field at the start of the input.
+    pub fn delimit(
+        length_field: LengthField,
+        options: &FieldOptions,
+        input: &[u8],
+    ) -> Result<Range<usize>, Error> {
+        length_field.delimit(input, options.byte_order)
+    }
+
+    /// Number of bytes read of a struct or union of `len` bytes including its padding.
+    pub fn padded_len(options: &FieldOptions, len: usize, input: &[u8]) -> usize {
+        options.padded_len(len, input)
+    }
+
+    /// Appends a struct preceded by the length field, its members are appended by `encode`.
+    ///
+    /// Structs with a length field are padded to the alignment of the options.
+    /// On error, the output is left unchanged.
+    pub fn encode_struct(
+        length_field: LengthField,
+        options: &FieldOptions,
+        output: &mut Vec<u8>,
+        encode: impl FnOnce(&mut Vec<u8>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let start = output.len();
+        let position = length_field.reserve(output);
+        let result =
+            encode(output).and_then(|()| length_field.finish(output, position, options.byte_order));
+        match result {
+            Ok(()) if length_field != LengthField::None => options.pad(output),
+            Ok(()) => {}
+            Err(_) => output.truncate(start),
+        }
+        result
+    }
+
+    /// Appends a padded union of the type selector, its member is appended by `encode`.
+    ///
+    /// On error, the output is left unchanged.
+    pub fn encode_union(
+        codec: &UnionCodec,
+        selector: u32,
+        options: &FieldOptions,
+        output: &mut Vec<u8>,
+        encode: impl FnOnce(&mut Vec<u8>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let position = output.len();
+        let result = codec.begin(selector, output).and_then(|start| {
+            encode(output)?;
+            codec.finish(output, start)
+        });
+        match result {
+            Ok(()) => options.pad(output),
+            Err(_) => output.truncate(position),
+        }
+        result
+    }
+
+    /// Decodes a padded union, its member is decoded by `decode`.
+    pub fn decode_union<'a, T>(
+        codec: &UnionCodec,
+        options: &FieldOptions,
+        input: &'a [u8],
+        decode: impl FnOnce(u32, &'a [u8]) -> Result<(T, usize), Error>,
+    ) -> Result<(T, usize), Error> {
+        let (value, len) = codec.decode_member(input, decode)?;
+        Ok((value, options.padded_len(len, input)))
+    }
+
+    /// Data id, wire type and encoded value of a TLV member
+    pub type Member = (u16, WireType, Vec<u8>);
+
+    /// Encodes a TLV member, its length field is given by the wire type.
+    ///
+    /// The value is padded relative to its own start, it is encoded without the payload.
+    pub fn encode_member<T: SomeIpSerialize + ?Sized>(
+        members: &mut Vec<Member>,
+        data_id: u16,
+        value: &T,
+        options: &FieldOptions,
+    ) -> Result<(), Error> {
+        let wire_type = match (T::WIRE_TYPE.fixed_size(), options.length_field) {
+            (Some(_), _) => T::WIRE_TYPE,
+            (None, Some(LengthField::U8)) => WireType::Dynamic8,
+            (None, Some(LengthField::U16)) => WireType::Dynamic16,
+            (None, _) => WireType::Dynamic32,
+        };
+        let options = FieldOptions {
+            length_field: Some(LengthField::None),
+            offset: Offset::default(),
+            ..*options
+        };
+        let mut output = Vec::new();
+        value.encode(&options, &mut output)?;
+        members.push((data_id, wire_type, output));
+        Ok(())
+    }
+
+    /// Appends a padded TLV encoded struct of the encoded members.
+    pub fn encode_tlv(
+        codec: &TlvCodec,
+        members: &[Member],
+        options: &FieldOptions,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let members: Vec<TlvMember> = members
+            .iter()
+            .map(|(data_id, wire_type, value)| TlvMember {
+                data_id: *data_id,
+                wire_type: *wire_type,
+                value,
+            })
+            .collect();
+        codec.encode(&members, output)?;
+        options.pad(output);
+        Ok(())
+    }
+
+    /// Decodes the TLV member with the data id, `None` if it is missing.
+    ///
+    /// The value is padded relative to its own start like by [`encode_member`].
+    pub fn decode_member<'a, T: SomeIpDeserialize<'a>>(
+        input: &'a [u8],
+        members: &[TlvMember<'a>],
+        data_id: u16,
+        segment: PathSegment,
+        options: &FieldOptions,
+    ) -> Result<Option<T>, Error> {
+        let Some(member) = members.iter().find(|member| member.data_id == data_id) else {
+            return Ok(None);
+        };
+        let offset = member.value.as_ptr() as usize - input.as_ptr() as usize;
+        let options = FieldOptions {
+            length_field: Some(LengthField::None),
+            offset: Offset::default(),
+            ..*options
+        };
+        T::decode(&options, member.value)
+            .map(|(value, _)| Some(value))
+            .map_err(|e| e.context(offset, segment))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_numbers() {
+        assert_eq!(Ok(vec![0x01]), true.to_payload());
+        assert_eq!(Ok(vec![0xFF, 0xFE]), (-2i16).to_payload());
+        assert_eq!(Ok(vec![0x3F, 0x80, 0x00, 0x00]), 1.0f32.to_payload());
+        assert_eq!(Ok(0x0102u16), u16::from_payload(&[0x01, 0x02]));
+        assert_eq!(Err(Error::InvalidBool(2)), bool::from_payload(&[0x02]));
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 4, actual: 3 }),
+            u32::from_payload(&[0x01, 0x02, 0x03])
+        );
+        assert_eq!(
+            Err(Error::TrailingBytes { offset: 1, len: 1 }),
+            u8::from_payload(&[0x01, 0x02])
+        );
+    }
+
+    #[test]
+    fn encode_and_decode_with_options() {
+        let options = FieldOptions {
+            length_field: Some(LengthField::U8),
+            max_elements: Some(2),
+            ..FieldOptions::default()
+        };
+
+        let mut output = Vec::new();
+        vec![1u16, 2].encode(&options, &mut output).unwrap();
+        assert_eq!(vec![0x04, 0x00, 0x01, 0x00, 0x02], output);
+        assert_eq!(
+            Ok((vec![1u16, 2], 5)),
+            Vec::<u16>::decode(&options, &output)
+        );
+        assert_eq!(
+            Err(Error::TooManyElements { max: 2, actual: 3 }),
+            <&[u8]>::decode(&options, &[0x03, 0x01, 0x02, 0x03])
+        );
+
+        let mut output = Vec::new();
+        "a".encode(&options, &mut output).unwrap();
+        assert_eq!(vec![0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00], output);
+        assert_eq!(Ok(("a", 6)), <&str>::decode(&options, &output));
+
+        let mut output = Vec::new();
+        [1u8, 2]
+            .encode(&FieldOptions::default(), &mut output)
+            .unwrap();
+        assert_eq!(vec![0x01, 0x02], output);
+        assert_eq!(Ok([1u8, 2]), <[u8; 2]>::from_payload(&output));
+    }
+
+    #[test]
+    fn byte_order_and_alignment() {
+        let options = FieldOptions {
+            byte_order: ByteOrder::LittleEndian,
+            alignment: 4,
+            ..FieldOptions::default()
+        };
+
+        let mut output = vec![0xAA];
+        vec![1u16].encode(&options, &mut output).unwrap();
+        assert_eq!(
+            vec![
+                0xAA, // preceding member
+                0x02, 0x00, 0x00, 0x00, 0x01, 0x00, // length(u32), values
+                0x00, // padding
+            ],
+            output
+        );
+        let member = options.member(1);
+        assert_eq!(
+            Ok((vec![1u16], 7)),
+            Vec::<u16>::decode(&member, &output[1..])
+        );
+        // missing padding at the end of the payload is tolerated
+        assert_eq!(
+            Ok((vec![1u16], 6)),
+            Vec::<u16>::decode(&member, &output[1..7])
+        );
+
+        // fixed length arrays are not padded
+        let mut output = Vec::new();
+        [1u16, 2].encode(&options, &mut output).unwrap();
+        assert_eq!(vec![0x01, 0x00, 0x02, 0x00], output);
+        assert_eq!(Ok(([1u16, 2], 4)), <[u16; 2]>::decode(&options, &output));
+    }
+
+    #[test]
+    fn borrowed_utf16_string() {
+        let options = FieldOptions {
+            encoding: Encoding::Utf16Be,
+            ..FieldOptions::default()
+        };
+        let input = [0x00, 0x00, 0x00, 0x06, 0xFE, 0xFF, 0x00, 0x61, 0x00, 0x00];
+
+        assert_eq!(
+            Ok((String::from("a"), 10)),
+            String::decode(&options, &input)
+        );
+        assert_eq!(
+            Err(Error::InvalidString("borrowed strings must be UTF-8")),
+            <&str>::decode(&options, &input)
+        );
+    }
+}
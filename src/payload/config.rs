@@ -0,0 +1,243 @@
+use super::Encoding;
+#[cfg(feature = "serde")]
+use super::{ArrayCodec, StringCodec, UnionCodec};
+use crate::Error;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Byte order of numbers and of length and type fields.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "schema",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ByteOrder {
+    /// Network byte order, the SOME/IP default
+    #[default]
+    BigEndian,
+    /// Little endian
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// Reads an unsigned integer of 1, 2 or 4 bytes.
+    pub(crate) fn read(&self, bytes: &[u8]) -> usize {
+        match (self, bytes) {
+            (_, [a]) => *a as usize,
+            (ByteOrder::BigEndian, [a, b]) => u16::from_be_bytes([*a, *b]) as usize,
+            (ByteOrder::LittleEndian, [a, b]) => u16::from_le_bytes([*a, *b]) as usize,
+            (ByteOrder::BigEndian, [a, b, c, d]) => u32::from_be_bytes([*a, *b, *c, *d]) as usize,
+            (ByteOrder::LittleEndian, [a, b, c, d]) => {
+                u32::from_le_bytes([*a, *b, *c, *d]) as usize
+            }
+            _ => unreachable!("integers are 1, 2 or 4 bytes"),
+        }
+    }
+
+    /// Writes an unsigned integer filling the field of 1, 2 or 4 bytes.
+    pub(crate) fn write(&self, value: usize, field: &mut [u8]) {
+        match (self, field.len()) {
+            (_, 0) => {}
+            (_, 1) => field[0] = value as u8,
+            (ByteOrder::BigEndian, 2) => field.copy_from_slice(&(value as u16).to_be_bytes()),
+            (ByteOrder::LittleEndian, 2) => field.copy_from_slice(&(value as u16).to_le_bytes()),
+            (ByteOrder::BigEndian, _) => field.copy_from_slice(&(value as u32).to_be_bytes()),
+            (ByteOrder::LittleEndian, _) => field.copy_from_slice(&(value as u32).to_le_bytes()),
+        }
+    }
+}
+
+/// Size of a length field preceding a dynamic length member.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum LengthField {
+    /// No length field
+    None,
+    /// 8 bit length field
+    U8,
+    /// 16 bit length field
+    U16,
+    /// 32 bit length field
+    U32,
+}
+
+impl LengthField {
+    /// Size of the length field in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            LengthField::None => 0,
+            LengthField::U8 => 1,
+            LengthField::U16 => 2,
+            LengthField::U32 => 4,
+        }
+    }
+
+    /// Largest length the field can hold
+    pub fn max(&self) -> usize {
+        match self {
+            LengthField::None => usize::MAX,
+            LengthField::U8 => u8::MAX as usize,
+            LengthField::U16 => u16::MAX as usize,
+            LengthField::U32 => u32::MAX as usize,
+        }
+    }
+
+    /// Reads the length field at the start of the input, `None` without a length field.
+    pub(crate) fn decode(
+        &self,
+        input: &[u8],
+        byte_order: ByteOrder,
+    ) -> Result<Option<usize>, Error> {
+        if input.len() < self.size() {
+            return Err(Error::NotEnoughData {
+                min: self.size(),
+                actual: input.len(),
+            });
+        }
+
+        Ok(match self {
+            LengthField::None => None,
+            _ => Some(byte_order.read(&input[..self.size()])),
+        })
+    }
+
+    /// Range of the bytes delimited by the length field at the start of the input.
+    ///
+    /// Without a length field the range extends to the end of the input.
+    pub(crate) fn delimit(
+        &self,
+        input: &[u8],
+        byte_order: ByteOrder,
+    ) -> Result<Range<usize>, Error> {
+        let start = self.size();
+        let end = match self.decode(input, byte_order)? {
+            Some(len) => start + len,
+            None => input.len(),
+        };
+        if end > input.len() {
+            return Err(Error::NotEnoughData {
+                min: end,
+                actual: input.len(),
+            });
+        }
+        Ok(start..end)
+    }
+
+    /// Appends a zeroed length field and returns its position.
+    pub(crate) fn reserve(&self, output: &mut Vec<u8>) -> usize {
+        let position = output.len();
+        output.resize(position + self.size(), 0);
+        position
+    }
+
+    /// Writes the number of bytes following the length field reserved at `position`.
+    pub(crate) fn finish(
+        &self,
+        output: &mut [u8],
+        position: usize,
+        byte_order: ByteOrder,
+    ) -> Result<(), Error> {
+        let len = output.len() - position - self.size();
+        self.write(output, position, len, byte_order)
+    }
+
+    /// Writes `len` into the length field reserved at `position`.
+    pub(crate) fn write(
+        &self,
+        output: &mut [u8],
+        position: usize,
+        len: usize,
+        byte_order: ByteOrder,
+    ) -> Result<(), Error> {
+        if len > self.max() {
+            return Err(Error::LengthFieldOverflow {
+                len,
+                max: self.max(),
+            });
+        }
+
+        byte_order.write(len, &mut output[position..position + self.size()]);
+        Ok(())
+    }
+}
+
+/// Configuration of the payload encoding.
+///
+/// The defaults follow PRS_SOMEIP: network byte order, no alignment, UTF-8
+/// strings, 32 bit length fields for dynamic length strings, arrays and
+/// unions, 32 bit union type fields and no length fields for structs.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Config {
+    /// Byte order of numbers and of length and type fields
+    pub byte_order: ByteOrder,
+    /// Alignment in bytes of members following variable size members.
+    ///
+    /// Strings, arrays, unions and structs with a length field are padded
+    /// with zeros to a multiple of `alignment` bytes from the start of the payload.
+    pub alignment: usize,
+    /// Character encoding of strings
+    pub string_encoding: Encoding,
+    /// Length field of dynamic length strings
+    pub string_length_field: LengthField,
+    /// Length field of dynamic length arrays, including byte arrays
+    pub array_length_field: LengthField,
+    /// Largest number of elements of dynamic length arrays
+    pub max_array_elements: Option<usize>,
+    /// Length field of structs
+    pub struct_length_field: LengthField,
+    /// Length field of unions
+    pub union_length_field: LengthField,
+    /// Type field of unions
+    pub union_type_field: LengthField,
+    /// Alignment of union members in bytes
+    pub union_alignment: usize,
+}
+
+impl Config {
+    /// Codec of dynamic length strings
+    #[cfg(feature = "serde")]
+    pub(crate) fn string_codec(&self) -> StringCodec {
+        StringCodec {
+            byte_order: self.byte_order,
+            ..StringCodec::dynamic(self.string_encoding, self.string_length_field)
+        }
+    }
+
+    /// Codec of dynamic length arrays
+    #[cfg(feature = "serde")]
+    pub(crate) fn array_codec(&self) -> ArrayCodec {
+        ArrayCodec {
+            max_elements: self.max_array_elements,
+            byte_order: self.byte_order,
+            ..ArrayCodec::dynamic(self.array_length_field)
+        }
+    }
+
+    /// Codec of unions
+    #[cfg(feature = "serde")]
+    pub(crate) fn union_codec(&self) -> UnionCodec {
+        UnionCodec {
+            length_field: self.union_length_field,
+            type_field: self.union_type_field,
+            alignment: self.union_alignment,
+            byte_order: self.byte_order,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            byte_order: ByteOrder::BigEndian,
+            alignment: 1,
+            string_encoding: Encoding::Utf8,
+            string_length_field: LengthField::U32,
+            array_length_field: LengthField::U32,
+            max_array_elements: None,
+            struct_length_field: LengthField::None,
+            union_length_field: LengthField::U32,
+            union_type_field: LengthField::U32,
+            union_alignment: 1,
+        }
+    }
+}
@@ -0,0 +1,817 @@
+use super::{ArrayCodec, ByteOrder, Config, LengthField};
+use crate::{
+    error::PathSegment::{Field, Index},
+    Error, RpcPayload,
+};
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use core::fmt::Display;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::Deserialize;
+
+/// Deserialize a value from a SOME/IP payload using the default configuration.
+///
+/// The payload must contain exactly this value, bytes after its end are
+/// reported as [`Error::TrailingBytes`].
+pub fn from_slice<'de, T: Deserialize<'de>>(input: RpcPayload<'de>) -> Result<T, Error> {
+    from_slice_with_config(input, Config::default())
+}
+
+/// Deserialize a value from a SOME/IP payload using the given configuration.
+pub fn from_slice_with_config<'de, T: Deserialize<'de>>(
+    input: RpcPayload<'de>,
+    config: Config,
+) -> Result<T, Error> {
+    let mut deserializer = Deserializer::new(input, config);
+    let value = T::deserialize(&mut deserializer)?;
+    if deserializer.offset() < input.len() {
+        return Err(Error::TrailingBytes {
+            offset: deserializer.offset(),
+            len: input.len() - deserializer.offset(),
+        });
+    }
+    Ok(value)
+}
+
+/// Serde deserializer decoding the SOME/IP payload wire format.
+///
+/// The counterpart of the [`Serializer`](super::Serializer). Strings and byte
+/// arrays are borrowed from the input. Errors of struct members and sequence
+/// elements are wrapped in [`Error::Parse`] with the offset and path of the field.
+/// Padding after variable size members is skipped according to the [`Config`].
+#[derive(Clone, Debug)]
+pub struct Deserializer<'de> {
+    input: RpcPayload<'de>,
+    offset: usize,
+    /// End of the innermost length delimited member
+    end: usize,
+    config: Config,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Creates a deserializer reading from the start of the payload.
+    pub fn new(input: RpcPayload<'de>, config: Config) -> Self {
+        Self {
+            input,
+            offset: 0,
+            end: input.len(),
+            config,
+        }
+    }
+
+    /// The offset of the next byte to be read
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Checks that `len` bytes are left, reporting the sizes relative to the offset.
+    fn check_remaining(&self, len: usize) -> Result<usize, Error> {
+        let remaining = self.end - self.offset;
+        if len > remaining {
+            return Err(Error::NotEnoughData {
+                min: len,
+                actual: remaining,
+            });
+        }
+        Ok(self.offset + len)
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'de [u8], Error> {
+        let end = self.check_remaining(len)?;
+
+        let bytes = &self.input[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.read(N)?);
+        Ok(bytes)
+    }
+
+    /// Reads a length field, without a length field the member extends to the end.
+    fn read_length(&mut self, length_field: LengthField) -> Result<usize, Error> {
+        let bytes = self.read(length_field.size())?;
+        let len = length_field.decode(bytes, self.config.byte_order)?;
+        Ok(len.unwrap_or(self.end - self.offset))
+    }
+
+    /// Skips the padding to the alignment after a variable size member.
+    ///
+    /// Padding missing at the end of the input or of a length delimited member is tolerated.
+    fn skip_padding(&mut self) {
+        let aligned = self.offset.next_multiple_of(self.config.alignment.max(1));
+        self.offset = aligned.min(self.end);
+    }
+
+    /// Reads a member preceded by a length field.
+    fn read_member(&mut self, length_field: LengthField) -> Result<&'de [u8], Error> {
+        let len = self.read_length(length_field)?;
+        self.read(len)
+    }
+
+    /// Runs `f` on the member preceded by a length field, skipping unread bytes.
+    fn with_member<T>(
+        &mut self,
+        length_field: LengthField,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if length_field == LengthField::None {
+            return f(self);
+        }
+
+        let len = self.read_length(length_field)?;
+        self.with_len(len, f)
+    }
+
+    /// Runs `f` on the next `len` bytes, skipping unread bytes.
+    fn with_len<T>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let end = self.check_remaining(len)?;
+        let outer_end = core::mem::replace(&mut self.end, end);
+        let value = f(self);
+        self.end = outer_end;
+        self.offset = end;
+        value
+    }
+
+    fn read_str(&mut self) -> Result<Cow<'de, str>, Error> {
+        let bytes = self.read_member(self.config.string_length_field)?;
+        let value = self.config.string_codec().decode_content(bytes)?;
+        self.skip_padding();
+        Ok(value)
+    }
+}
+
+/// Number of members of a sequence, tuple or struct
+enum Count {
+    /// Fixed number of members
+    Fixed(usize),
+    /// Dynamic length array extending to the end of its length field
+    Array(ArrayCodec),
+}
+
+/// Reads the members of sequences, tuples and structs.
+struct Members<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    /// Names of struct members
+    fields: &'static [&'static str],
+    len: Count,
+    index: usize,
+}
+
+impl<'a, 'de> Members<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, fields: &'static [&'static str], len: Count) -> Self {
+        Self {
+            de,
+            fields,
+            len,
+            index: 0,
+        }
+    }
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Members<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.len {
+            Count::Fixed(len) if self.index == len => return Ok(None),
+            Count::Array(array) if self.de.offset == self.de.end => {
+                array.check_len(self.index)?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let offset = self.de.offset;
+        let segment = match self.fields.get(self.index) {
+            Some(name) => Field(name),
+            None => Index(self.index),
+        };
+        self.index += 1;
+        let value = seed
+            .deserialize(&mut *self.de)
+            .map_err(|e| e.context(offset, segment.clone()))?;
+        // an element without bytes would never reach the end of the array
+        if matches!(self.len, Count::Array(_)) && self.de.offset == offset {
+            return Err(Error::ZeroSizedElement.context(offset, segment));
+        }
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.len {
+            Count::Fixed(len) => Some(len - self.index),
+            Count::Array(_) => None,
+        }
+    }
+}
+
+/// Reads a union, selecting the enum variant by the type selector.
+struct UnionAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    /// Length of the selected member, `None` without length field
+    len: Option<usize>,
+}
+
+impl<'a, 'de> UnionAccess<'a, 'de> {
+    /// Runs `f` on the member, skipping its padding.
+    fn member<T>(
+        self,
+        f: impl FnOnce(&mut Deserializer<'de>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if let Some(len) = self.len {
+            return self.de.with_len(len, f);
+        }
+
+        let start = self.de.offset;
+        let value = f(self.de)?;
+        let alignment = self.de.config.union_alignment.max(1);
+        let end = start + (self.de.offset - start).next_multiple_of(alignment);
+        self.de.offset = end.min(self.de.end);
+        Ok(value)
+    }
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for UnionAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let codec = self.de.config.union_codec();
+        let len = match codec.length_field {
+            LengthField::None => None,
+            length_field => Some(self.de.read_length(length_field)?),
+        };
+        let selector = codec.decode_selector(self.de.read(codec.type_field.size())?)?;
+        // the inverse of the serializer, the type selector 0 of the empty union has no variant
+        let variant_index = selector
+            .checked_sub(1)
+            .ok_or(Error::InvalidTypeSelector(selector))?;
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(variant_index))?;
+        Ok((variant, UnionAccess { de: self.de, len }))
+    }
+}
+
+impl<'a, 'de> de::VariantAccess<'de> for UnionAccess<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        self.member(|_| Ok(()))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        self.member(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.member(|de| visitor.visit_seq(Members::new(de, &[], Count::Fixed(len))))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.member(|de| visitor.visit_seq(Members::new(de, fields, Count::Fixed(fields.len()))))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+/// Deserializes numbers in the byte order of the configuration.
+macro_rules! deserialize_numbers {
+    ($($method:ident => $visit:ident($ty:ty)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let bytes = self.read_array()?;
+                visitor.$visit(match self.config.byte_order {
+                    ByteOrder::BigEndian => <$ty>::from_be_bytes(bytes),
+                    ByteOrder::LittleEndian => <$ty>::from_le_bytes(bytes),
+                })
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::UnsupportedType("any"))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.read(1)?[0] {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            value => Err(Error::InvalidBool(value)),
+        }
+    }
+
+    deserialize_numbers!(
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64)
+    );
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.read_str()? {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let bytes = self.read_member(self.config.array_length_field)?;
+        self.config.array_codec().check_len(bytes.len())?;
+        self.skip_padding();
+        visitor.visit_borrowed_bytes(bytes)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::UnsupportedType("option"))
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let array = self.config.array_codec();
+        let value = self.with_member(array.length_field, |de| {
+            visitor.visit_seq(Members::new(de, &[], Count::Array(array)))
+        })?;
+        self.skip_padding();
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_seq(Members::new(self, &[], Count::Fixed(len)))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::UnsupportedType("map"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let length_field = self.config.struct_length_field;
+        let value = self.with_member(length_field, |de| {
+            visitor.visit_seq(Members::new(de, fields, Count::Fixed(fields.len())))
+        })?;
+        if length_field != LengthField::None {
+            self.skip_padding();
+        }
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let value = visitor.visit_enum(UnionAccess {
+            de: &mut *self,
+            len: None,
+        })?;
+        self.skip_padding();
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::UnsupportedType("identifier"))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::UnsupportedType("any"))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{to_vec, to_vec_with_config, Encoding};
+    use crate::PathSegment;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Position {
+        x: i16,
+        y: u32,
+        valid: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Track<'a> {
+        id: u8,
+        name: &'a str,
+        data: &'a [u8],
+        positions: Vec<Position>,
+        matrix: [[u8; 2]; 2],
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let input: &[u8] = &[
+            0x07, // id(u8)
+            0x00, 0x00, 0x00, 0x06, // length(u32)
+            0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00, // bom, "ab", terminator
+            0x00, 0x00, 0x00, 0x02, // length(u32)
+            0xAA, 0xBB, // data
+            0x00, 0x00, 0x00, 0x0E, // length(u32)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, // x(i16), y(u32), valid(bool)
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x03, 0x00, // x(i16), y(u32), valid(bool)
+            0x01, 0x02, 0x03, 0x04, // matrix([[u8; 2]; 2])
+        ];
+
+        let track: Track = from_slice(input).unwrap();
+        assert_eq!(
+            Track {
+                id: 0x07,
+                name: "ab",
+                data: &[0xAA, 0xBB],
+                positions: vec![
+                    Position {
+                        x: 1,
+                        y: 2,
+                        valid: true,
+                    },
+                    Position {
+                        x: -1,
+                        y: 3,
+                        valid: false,
+                    },
+                ],
+                matrix: [[0x01, 0x02], [0x03, 0x04]],
+            },
+            track
+        );
+        assert_eq!(input, to_vec(&track).unwrap());
+    }
+
+    #[test]
+    fn deserialize_with_length_fields() {
+        let config = Config {
+            string_length_field: LengthField::U8,
+            array_length_field: LengthField::U16,
+            struct_length_field: LengthField::U8,
+            ..Config::default()
+        };
+
+        let position = Position {
+            x: 1,
+            y: 2,
+            valid: true,
+        };
+        let input = to_vec_with_config(&position, config).unwrap();
+        assert_eq!(
+            Ok(position),
+            from_slice_with_config::<Position>(&input, config)
+        );
+
+        // unknown members appended to an extensible struct are skipped
+        let input = [0x08, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, 0xFF, 0x05];
+        let (position, next): (Position, u8) = from_slice_with_config(&input, config).unwrap();
+        assert_eq!(2, position.y);
+        assert_eq!(0x05, next);
+
+        let input = [0x05, 0xEF, 0xBB, 0xBF, 0x78, 0x00];
+        assert_eq!(Ok("x"), from_slice_with_config::<&str>(&input, config));
+        assert_eq!(Ok('x'), from_slice_with_config::<char>(&input, config));
+    }
+
+    #[test]
+    fn deserialize_field_errors() {
+        let input: &[u8] = &[
+            0x00, 0x00, 0x00, 0x0E, // length(u32)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, // x(i16), y(u32), valid(bool)
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x03, 0x02, // x(i16), y(u32), valid(bool)
+        ];
+
+        let error = from_slice::<Vec<Position>>(input).unwrap_err();
+        assert_eq!(Some(17), error.offset());
+        assert_eq!("[1].valid", error.path().unwrap().to_string());
+        assert_eq!(
+            &[PathSegment::Index(1), PathSegment::Field("valid")],
+            error.path().unwrap().segments()
+        );
+        assert_eq!(&Error::InvalidBool(0x02), error.root_cause());
+
+        assert_eq!(
+            Err(Error::NotEnoughData {
+                min: 14,
+                actual: 12
+            }),
+            from_slice::<Vec<Position>>(&input[..16])
+        );
+
+        let error = from_slice::<Position>(&input[4..9]).unwrap_err();
+        assert_eq!(Some(2), error.offset());
+        assert_eq!("y", error.path().unwrap().to_string());
+        assert_eq!(
+            &Error::NotEnoughData { min: 4, actual: 3 },
+            error.root_cause()
+        );
+    }
+
+    #[test]
+    fn deserialize_invalid_strings() {
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 4, actual: 3 }),
+            from_slice::<&str>(&[0x00, 0x00, 0x00, 0x03, 0xEF, 0xBB, 0xBF])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("invalid byte order mark")),
+            from_slice::<&str>(&[0x00, 0x00, 0x00, 0x04, 0xFE, 0xFF, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("missing terminator")),
+            from_slice::<&str>(&[0x00, 0x00, 0x00, 0x04, 0xEF, 0xBB, 0xBF, 0x61])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("invalid UTF-8")),
+            from_slice::<&str>(&[0x00, 0x00, 0x00, 0x05, 0xEF, 0xBB, 0xBF, 0xFF, 0x00])
+        );
+    }
+
+    #[test]
+    fn deserialize_max_array_elements() {
+        let config = Config {
+            max_array_elements: Some(2),
+            ..Config::default()
+        };
+        let input = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+
+        assert_eq!(
+            Err(Error::TooManyElements { max: 2, actual: 3 }),
+            from_slice_with_config::<Vec<u8>>(&input, config)
+        );
+        assert_eq!(
+            Err(Error::TooManyElements { max: 2, actual: 3 }),
+            from_slice_with_config::<&[u8]>(&input, config)
+        );
+        assert_eq!(
+            Err(Error::TooManyElements { max: 2, actual: 3 }),
+            to_vec_with_config(&vec![1u8, 2, 3], config)
+        );
+
+        let matrix = vec![vec![1u8, 2], vec![3]];
+        let input = to_vec_with_config(&matrix, config).unwrap();
+        assert_eq!(
+            vec![
+                0x00, 0x00, 0x00, 0x0B, // length(u32)
+                0x00, 0x00, 0x00, 0x02, 0x01, 0x02, // length(u32), [0][0], [0][1]
+                0x00, 0x00, 0x00, 0x01, 0x03, // length(u32), [1][0]
+            ],
+            input
+        );
+        assert_eq!(Ok(matrix), from_slice_with_config(&input, config));
+    }
+
+    #[test]
+    fn deserialize_zero_sized_elements() {
+        assert_eq!(Ok(vec![]), from_slice::<Vec<()>>(&[0x00, 0x00, 0x00, 0x00]));
+
+        let error = from_slice::<Vec<()>>(&[0x00, 0x00, 0x00, 0x01, 0x00]).unwrap_err();
+        assert_eq!(Some(4), error.offset());
+        assert_eq!("[0]", error.path().unwrap().to_string());
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+
+        // fixed size sequences of zero sized elements are fine
+        assert_eq!(Ok([(), ()]), from_slice::<[(); 2]>(&[]));
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    enum Shape {
+        Empty,
+        Circle(u8),
+        Rectangle(u8, u8),
+        Polygon { corners: u16 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    enum Known {
+        Empty,
+        Circle(u8),
+        #[serde(other)]
+        Unknown,
+    }
+
+    #[test]
+    fn deserialize_union() {
+        let config = Config {
+            union_length_field: LengthField::U8,
+            union_type_field: LengthField::U8,
+            union_alignment: 2,
+            ..Config::default()
+        };
+        let input = to_vec_with_config(
+            &vec![
+                Shape::Empty,
+                Shape::Circle(3),
+                Shape::Rectangle(1, 2),
+                Shape::Polygon { corners: 5 },
+            ],
+            config,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                0x00, 0x00, 0x00, 0x0E, // length(u32)
+                0x00, 0x01, // length(u8), type(u8)
+                0x02, 0x02, 0x03, 0x00, // length(u8), type(u8), radius(u8), padding
+                0x02, 0x03, 0x01, 0x02, // length(u8), type(u8), width(u8), height(u8)
+                0x02, 0x04, 0x00, 0x05, // length(u8), type(u8), corners(u16)
+            ],
+            input
+        );
+        assert_eq!(
+            Ok(vec![
+                Shape::Empty,
+                Shape::Circle(3),
+                Shape::Rectangle(1, 2),
+                Shape::Polygon { corners: 5 },
+            ]),
+            from_slice_with_config(&input, config)
+        );
+
+        // unknown type selectors fall back to the `other` variant
+        assert_eq!(
+            Ok(vec![
+                Known::Empty,
+                Known::Circle(3),
+                Known::Unknown,
+                Known::Unknown
+            ]),
+            from_slice_with_config(&input, config)
+        );
+
+        // the type selector 0 of the empty union has no variant
+        assert_eq!(
+            Err(Error::InvalidTypeSelector(0)),
+            from_slice_with_config::<Shape>(&[0x00, 0x00], config)
+        );
+    }
+
+    #[test]
+    fn deserialize_union_without_length_field() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Outer {
+            shape: Shape,
+            x: u8,
+        }
+
+        let config = Config {
+            union_length_field: LengthField::None,
+            union_type_field: LengthField::U8,
+            union_alignment: 4,
+            ..Config::default()
+        };
+        let value = Outer {
+            shape: Shape::Circle(7),
+            x: 9,
+        };
+        let input = to_vec_with_config(&value, config).unwrap();
+        assert_eq!(
+            vec![
+                0x02, 0x07, 0x00, 0x00, 0x00, // type(u8), radius(u8), padding
+                0x09, // x(u8)
+            ],
+            input
+        );
+        assert_eq!(Ok(value), from_slice_with_config(&input, config));
+    }
+
+    #[test]
+    fn deserialize_utf16_string() {
+        let config = Config {
+            string_encoding: Encoding::Utf16Le,
+            ..Config::default()
+        };
+        let input = to_vec_with_config("ab", config).unwrap();
+        assert_eq!(
+            vec![0x00, 0x00, 0x00, 0x08, 0xFF, 0xFE, 0x61, 0x00, 0x62, 0x00, 0x00, 0x00],
+            input
+        );
+        assert_eq!(
+            Ok(String::from("ab")),
+            from_slice_with_config(&input, config)
+        );
+    }
+
+    #[test]
+    fn deserialize_little_endian_with_alignment() {
+        let config = Config {
+            byte_order: ByteOrder::LittleEndian,
+            alignment: 4,
+            ..Config::default()
+        };
+        let input: &[u8] = &[
+            0x07, // id(u8)
+            0x06, 0x00, 0x00, 0x00, // length(u32)
+            0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00, 0x00, // bom, "ab", terminator, padding
+            0x02, 0x00, 0x00, 0x00, // length(u32)
+            0xAA, 0xBB, 0x00, 0x00, // data, padding
+            0x0E, 0x00, 0x00, 0x00, // length(u32)
+            0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, // x(i16), y(u32), valid(bool)
+            0xFF, 0xFF, 0x03, 0x00, 0x00, 0x00, 0x00, // x(i16), y(u32), valid(bool)
+            0x00, 0x00, // padding
+            0x01, 0x02, 0x03, 0x04, // matrix([[u8; 2]; 2])
+        ];
+
+        let track: Track = from_slice_with_config(input, config).unwrap();
+        assert_eq!("ab", track.name);
+        assert_eq!(&[0xAA, 0xBB], track.data);
+        assert_eq!(-1, track.positions[1].x);
+        assert_eq!(3, track.positions[1].y);
+        assert_eq!([[0x01, 0x02], [0x03, 0x04]], track.matrix);
+        assert_eq!(input, to_vec_with_config(&track, config).unwrap());
+
+        // padding at the end of the payload may be missing
+        let input = [0x05, 0x00, 0x00, 0x00, 0xEF, 0xBB, 0xBF, 0x78, 0x00];
+        assert_eq!(Ok("x"), from_slice_with_config::<&str>(&input, config));
+        let input = to_vec_with_config("x", config).unwrap();
+        assert_eq!(12, input.len());
+        assert_eq!(Ok("x"), from_slice_with_config::<&str>(&input, config));
+    }
+
+    #[test]
+    fn deserialize_trailing_bytes() {
+        assert_eq!(Ok(0x0102), from_slice::<u16>(&[0x01, 0x02]));
+        assert_eq!(
+            Err(Error::TrailingBytes { offset: 2, len: 1 }),
+            from_slice::<u16>(&[0x01, 0x02, 0x03])
+        );
+        assert_eq!(
+            Err(Error::UnsupportedType("option")),
+            from_slice::<Option<u8>>(&[0x01])
+        );
+    }
+}
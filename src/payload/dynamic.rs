@@ -0,0 +1,857 @@
+use super::{ArrayCodec, ByteOrder, LengthField, StringCodec, UnionCodec};
+use crate::{
+    error::PathSegment::{Index, Member as MemberName},
+    Error, Message, MessageId, MessageType, MethodId, ServiceId,
+};
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+
+macro_rules! numbers {
+    ($($variant:ident($ty:ty)),*) => {
+        /// Type of a number.
+        #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+        pub enum NumberType {
+            $(
+                #[doc = concat!("`", stringify!($ty), "`")]
+                $variant,
+            )*
+        }
+
+        /// A decoded number.
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        pub enum Number {
+            $(
+                #[doc = concat!("`", stringify!($ty), "`")]
+                $variant($ty),
+            )*
+        }
+
+        impl NumberType {
+            /// Size of the number in bytes
+            pub fn size(&self) -> usize {
+                match self {
+                    $(NumberType::$variant => core::mem::size_of::<$ty>(),)*
+                }
+            }
+
+            fn decode(&self, bytes: &[u8], byte_order: ByteOrder) -> Number {
+                match (self, byte_order) {
+                    $(
+                        (NumberType::$variant, ByteOrder::BigEndian) => {
+                            Number::$variant(<$ty>::from_be_bytes(bytes.try_into().unwrap()))
+                        }
+                        (NumberType::$variant, ByteOrder::LittleEndian) => {
+                            Number::$variant(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl Number {
+            /// The type of the number
+            pub fn number_type(&self) -> NumberType {
+                match self {
+                    $(Number::$variant(_) => NumberType::$variant,)*
+                }
+            }
+
+            fn encode(&self, byte_order: ByteOrder, output: &mut Vec<u8>) {
+                match (self, byte_order) {
+                    $(
+                        (Number::$variant(value), ByteOrder::BigEndian) => {
+                            output.extend_from_slice(&value.to_be_bytes())
+                        }
+                        (Number::$variant(value), ByteOrder::LittleEndian) => {
+                            output.extend_from_slice(&value.to_le_bytes())
+                        }
+                    )*
+                }
+            }
+        }
+    };
+}
+
+numbers!(
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64)
+);
+
+/// Data type of a payload, described at runtime.
+///
+/// Arrays nest one data type per dimension, strings, arrays and unions are
+/// encoded with the codecs of the same name. Strings, dynamic length arrays,
+/// unions and structs with a length field are padded with zeros to a multiple
+/// of the alignment of the enclosing struct, see [`StructType::alignment`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DataType {
+    /// Boolean of one byte
+    Bool,
+    /// Number in the given byte order
+    Number(NumberType, ByteOrder),
+    /// String
+    String(StringCodec),
+    /// Array of elements of the same data type
+    Array(ArrayCodec, Box<DataType>),
+    /// Struct
+    Struct(StructType),
+    /// Union
+    Union(UnionCodec, Vec<UnionVariant>),
+}
+
+/// Members of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructType {
+    /// Length field preceding the members
+    pub length_field: LengthField,
+    /// Byte order of the length field
+    pub byte_order: ByteOrder,
+    /// Alignment in bytes from the start of the payload of members following
+    /// variable size members, `None` inherits the alignment of the enclosing
+    /// struct, which is 1 at the top.
+    pub alignment: Option<usize>,
+    /// Members in the order of the encoding
+    pub members: Vec<Member>,
+}
+
+/// Named member of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    /// Name of the member
+    pub name: String,
+    /// Data type of the member
+    pub data_type: DataType,
+}
+
+/// Member of a union.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnionVariant {
+    /// Type selector, 0 is reserved for the empty union
+    pub selector: u32,
+    /// Name of the member
+    pub name: String,
+    /// Data type of the member
+    pub data_type: DataType,
+}
+
+/// A decoded value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Boolean
+    Bool(bool),
+    /// Number
+    Number(Number),
+    /// String
+    String(String),
+    /// Array elements
+    Array(Vec<Node>),
+    /// Struct members
+    Struct(Vec<Field>),
+    /// Type selector and member of a union, `None` for the empty union
+    Union(u32, Option<Box<Field>>),
+    /// Type selector and member data of a union with an unknown type selector,
+    /// including the padding of the member
+    UnknownUnion(u32, Vec<u8>),
+}
+
+impl Value {
+    /// The member of a struct or union with the given name
+    pub fn field(&self, name: &str) -> Option<&Node> {
+        match self {
+            Value::Struct(fields) => fields.iter().find(|field| field.name == name),
+            Value::Union(_, Some(field)) if field.name == name => Some(field.as_ref()),
+            _ => None,
+        }
+        .map(|field| &field.node)
+    }
+}
+
+/// A decoded value and its location within the payload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// Offset of the value in bytes from the start of the payload
+    pub offset: usize,
+    /// Size of the value in bytes, including length fields and padding
+    pub len: usize,
+    /// The value
+    pub value: Value,
+}
+
+/// Named member of a struct or union value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// Name of the member
+    pub name: String,
+    /// Value of the member
+    pub node: Node,
+}
+
+impl DataType {
+    /// Decodes a value from the start of the input.
+    ///
+    /// Errors carry the offset and the path of the invalid value, the path
+    /// holds the names of struct and union members and the indices of array elements.
+    /// Unions with an unknown type selector are decoded to [`Value::UnknownUnion`].
+    pub fn decode(&self, input: &[u8]) -> Result<Node, Error> {
+        self.decode_at(input, 0, 1)
+    }
+
+    /// Decodes a value located at `offset` within the payload, padded to `alignment`.
+    fn decode_at(&self, input: &[u8], offset: usize, alignment: usize) -> Result<Node, Error> {
+        let alignment = self.alignment(alignment);
+        let (value, len) = match self {
+            DataType::Bool => match input.first() {
+                Some(0x00) => (Value::Bool(false), 1),
+                Some(0x01) => (Value::Bool(true), 1),
+                Some(value) => return Err(Error::InvalidBool(*value)),
+                None => return Err(Error::NotEnoughData { min: 1, actual: 0 }),
+            },
+            DataType::Number(number_type, byte_order) => {
+                let size = number_type.size();
+                match input.get(..size) {
+                    Some(bytes) => (Value::Number(number_type.decode(bytes, *byte_order)), size),
+                    None => {
+                        return Err(Error::NotEnoughData {
+                            min: size,
+                            actual: input.len(),
+                        })
+                    }
+                }
+            }
+            DataType::String(codec) => {
+                let (value, len) = codec.decode(input)?;
+                (Value::String(value.into_owned()), len)
+            }
+            DataType::Array(codec, element) => {
+                let (elements, len) = codec.decode(input, |data| {
+                    let start = offset + (data.as_ptr() as usize - input.as_ptr() as usize);
+                    let node = element.decode_at(data, start, alignment)?;
+                    let len = node.len;
+                    Ok((node, len))
+                })?;
+                (Value::Array(elements), len)
+            }
+            DataType::Struct(struct_type) => {
+                let range = struct_type
+                    .length_field
+                    .delimit(input, struct_type.byte_order)?;
+                let mut position = range.start;
+                let mut fields = Vec::with_capacity(struct_type.members.len());
+                for member in &struct_type.members {
+                    let node = member
+                        .data_type
+                        .decode_at(&input[position..range.end], offset + position, alignment)
+                        .map_err(|e| {
+                            e.context(offset + position, MemberName(member.name.clone()))
+                        })?;
+                    position += node.len;
+                    fields.push(Field {
+                        name: member.name.clone(),
+                        node,
+                    });
+                }
+                let len = match struct_type.length_field {
+                    LengthField::None => position,
+                    _ => range.end,
+                };
+                (Value::Struct(fields), len)
+            }
+            DataType::Union(codec, variants) => codec.decode_member(input, |selector, data| {
+                if selector == 0 {
+                    return Ok((Value::Union(0, None), 0));
+                }
+                let Some(variant) = variants.iter().find(|variant| variant.selector == selector)
+                else {
+                    return Ok((Value::UnknownUnion(selector, data.to_vec()), data.len()));
+                };
+                let start = offset + codec.length_field.size() + codec.type_field.size();
+                let node = variant
+                    .data_type
+                    .decode_at(data, start, alignment)
+                    .map_err(|e| e.context(start, MemberName(variant.name.clone())))?;
+                let len = node.len;
+                let field = Field {
+                    name: variant.name.clone(),
+                    node,
+                };
+                Ok((Value::Union(selector, Some(Box::new(field))), len))
+            })?,
+        };
+        let len = match self.is_padded() {
+            // padding missing at the end of the input is tolerated
+            true => ((offset + len).next_multiple_of(alignment) - offset).min(input.len()),
+            false => len,
+        };
+        Ok(Node { offset, len, value })
+    }
+
+    /// Appends the encoded value.
+    ///
+    /// Struct members are looked up by name, fields the struct does not declare are
+    /// rejected, the offsets of the nodes are ignored. Padding is aligned to the start
+    /// of the output. On error, the output is left unchanged.
+    pub fn encode(&self, value: &Value, output: &mut Vec<u8>) -> Result<(), Error> {
+        let position = output.len();
+        let result = self.encode_at(value, output, 1);
+        if result.is_err() {
+            output.truncate(position);
+        }
+        result
+    }
+
+    fn encode_at(
+        &self,
+        value: &Value,
+        output: &mut Vec<u8>,
+        alignment: usize,
+    ) -> Result<(), Error> {
+        let alignment = self.alignment(alignment);
+        match (self, value) {
+            (DataType::Bool, Value::Bool(value)) => output.push(*value as u8),
+            (DataType::Number(number_type, byte_order), Value::Number(number))
+                if number.number_type() == *number_type =>
+            {
+                number.encode(*byte_order, output)
+            }
+            (DataType::String(codec), Value::String(value)) => codec.encode(value, output)?,
+            (DataType::Array(codec, element), Value::Array(elements)) => {
+                let mut index = 0;
+                codec.encode(elements, output, |node, output| {
+                    let offset = output.len();
+                    element
+                        .encode_at(&node.value, output, alignment)
+                        .map_err(|e| e.context(offset, Index(index)))?;
+                    index += 1;
+                    Ok(())
+                })?
+            }
+            (DataType::Struct(struct_type), Value::Struct(fields)) => {
+                if let Some(field) = fields.iter().find(|field| {
+                    !struct_type
+                        .members
+                        .iter()
+                        .any(|member| member.name == field.name)
+                }) {
+                    return Err(Error::InvalidValue("unknown struct member")
+                        .context(output.len(), MemberName(field.name.clone())));
+                }
+                let position = struct_type.length_field.reserve(output);
+                for member in &struct_type.members {
+                    let offset = output.len();
+                    fields
+                        .iter()
+                        .find(|field| field.name == member.name)
+                        .ok_or(Error::InvalidValue("missing struct member"))
+                        .and_then(|field| {
+                            member
+                                .data_type
+                                .encode_at(&field.node.value, output, alignment)
+                        })
+                        .map_err(|e| e.context(offset, MemberName(member.name.clone())))?;
+                }
+                struct_type
+                    .length_field
+                    .finish(output, position, struct_type.byte_order)?
+            }
+            (DataType::Union(codec, _), Value::Union(0, None)) => {
+                let start = codec.begin(0, output)?;
+                codec.finish(output, start)?
+            }
+            (DataType::Union(codec, variants), Value::Union(selector, Some(field))) => {
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.selector == *selector && *selector != 0)
+                    .ok_or(Error::InvalidTypeSelector(*selector))?;
+                let start = codec.begin(*selector, output)?;
+                variant
+                    .data_type
+                    .encode_at(&field.node.value, output, alignment)
+                    .map_err(|e| e.context(start, MemberName(variant.name.clone())))?;
+                codec.finish(output, start)?
+            }
+            (DataType::Union(codec, _), Value::UnknownUnion(selector, data)) => {
+                if *selector == 0 && !data.is_empty() {
+                    return Err(Error::InvalidTypeSelector(0));
+                }
+                let start = codec.begin(*selector, output)?;
+                output.extend_from_slice(data);
+                codec.finish(output, start)?
+            }
+            (data_type, _) => return Err(Error::InvalidValue(data_type.name())),
+        }
+        if self.is_padded() {
+            output.resize(output.len().next_multiple_of(alignment), 0x00);
+        }
+        Ok(())
+    }
+
+    /// Alignment of the members of structs, inheriting `alignment`
+    fn alignment(&self, alignment: usize) -> usize {
+        match self {
+            DataType::Struct(StructType {
+                alignment: Some(alignment),
+                ..
+            }) => (*alignment).max(1),
+            _ => alignment,
+        }
+    }
+
+    /// Whether the value is padded to the alignment, i.e. is of variable size
+    fn is_padded(&self) -> bool {
+        match self {
+            DataType::Bool | DataType::Number(..) => false,
+            DataType::String(_) | DataType::Union(..) => true,
+            DataType::Array(codec, _) => codec.fixed_len().is_none(),
+            DataType::Struct(struct_type) => struct_type.length_field != LengthField::None,
+        }
+    }
+
+    /// Name of the kind of data type
+    fn name(&self) -> &'static str {
+        match self {
+            DataType::Bool => "bool",
+            DataType::Number(..) => "number of the data type",
+            DataType::String(_) => "string",
+            DataType::Array(..) => "array",
+            DataType::Struct(_) => "struct",
+            DataType::Union(..) => "union",
+        }
+    }
+}
+
+/// Data types of payloads, keyed by service id, method id and message type.
+///
+/// Decodes the payloads of `Message::Rpc` into trees of named values and
+/// encodes values into payloads.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PayloadTypes {
+    types: BTreeMap<(ServiceId, MethodId, u8), DataType>,
+}
+
+impl PayloadTypes {
+    /// Creates an empty set of payload types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the data type of the payload of a message, returns the previous data type.
+    pub fn insert(
+        &mut self,
+        message_id: MessageId,
+        message_type: MessageType,
+        data_type: DataType,
+    ) -> Option<DataType> {
+        self.types.insert(
+            (
+                message_id.service_id,
+                message_id.method_id,
+                message_type.into(),
+            ),
+            data_type,
+        )
+    }
+
+    /// The data type of the payload of a message
+    pub fn get(&self, message_id: &MessageId, message_type: MessageType) -> Option<&DataType> {
+        self.types.get(&(
+            message_id.service_id,
+            message_id.method_id,
+            message_type.into(),
+        ))
+    }
+
+    /// Decodes the payload of a `Message::Rpc`.
+    ///
+    /// Fails with [`Error::UnknownPayloadType`] if there is no data type for
+    /// the message and with [`Error::TrailingBytes`] if the payload exceeds its value.
+    pub fn decode(&self, message: &Message) -> Result<Node, Error> {
+        let Message::Rpc(header, payload) = message else {
+            return Err(Error::UnsupportedType("message without RPC payload"));
+        };
+        let node = self
+            .data_type(header.message_id(), header.message_type())?
+            .decode(payload)?;
+        match payload.len() - node.len {
+            0 => Ok(node),
+            len => Err(Error::TrailingBytes {
+                offset: node.len,
+                len,
+            }),
+        }
+    }
+
+    /// Encodes the payload of a message.
+    pub fn encode(
+        &self,
+        message_id: &MessageId,
+        message_type: MessageType,
+        value: &Value,
+    ) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        self.data_type(message_id, message_type)?
+            .encode(value, &mut output)?;
+        Ok(output)
+    }
+
+    fn data_type(
+        &self,
+        message_id: &MessageId,
+        message_type: MessageType,
+    ) -> Result<&DataType, Error> {
+        self.get(message_id, message_type)
+            .ok_or(Error::UnknownPayloadType {
+                service_id: message_id.service_id,
+                method_id: message_id.method_id,
+                message_type,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{payload::Encoding, RequestId};
+    use alloc::vec;
+
+    fn member(name: &str, data_type: DataType) -> Member {
+        Member {
+            name: name.into(),
+            data_type,
+        }
+    }
+
+    fn status_type() -> DataType {
+        DataType::Struct(StructType {
+            members: vec![
+                member(
+                    "id",
+                    DataType::Number(NumberType::U16, ByteOrder::BigEndian),
+                ),
+                member(
+                    "name",
+                    DataType::String(StringCodec::dynamic(Encoding::Utf8, LengthField::U8)),
+                ),
+                member(
+                    "values",
+                    DataType::Array(
+                        ArrayCodec::dynamic(LengthField::U8),
+                        Box::new(DataType::Number(NumberType::I16, ByteOrder::LittleEndian)),
+                    ),
+                ),
+                member(
+                    "state",
+                    DataType::Union(
+                        UnionCodec {
+                            length_field: LengthField::U8,
+                            type_field: LengthField::U8,
+                            ..UnionCodec::default()
+                        },
+                        vec![
+                            UnionVariant {
+                                selector: 1,
+                                name: "active".into(),
+                                data_type: DataType::Bool,
+                            },
+                            UnionVariant {
+                                selector: 2,
+                                name: "error".into(),
+                                data_type: DataType::Number(NumberType::U8, ByteOrder::BigEndian),
+                            },
+                        ],
+                    ),
+                ),
+            ],
+            length_field: LengthField::None,
+            byte_order: ByteOrder::BigEndian,
+            alignment: None,
+        })
+    }
+
+    const STATUS: &[u8] = &[
+        0x12, 0x34, // id(u16)
+        0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), name
+        0x04, 0x01, 0x00, 0xFF, 0xFF, // length(u8), values(i16 little endian)
+        0x01, 0x02, 0x07, // length(u8), type(u8), error(u8)
+    ];
+
+    #[test]
+    fn decode_and_encode() {
+        let data_type = status_type();
+        let node = data_type.decode(STATUS).unwrap();
+        assert_eq!(0, node.offset);
+        assert_eq!(STATUS.len(), node.len);
+
+        let id = node.value.field("id").unwrap();
+        assert_eq!((0, 2), (id.offset, id.len));
+        assert_eq!(Value::Number(Number::U16(0x1234)), id.value);
+        assert_eq!(
+            Value::String("a".into()),
+            node.value.field("name").unwrap().value
+        );
+
+        let Value::Array(values) = &node.value.field("values").unwrap().value else {
+            panic!("values is not an array");
+        };
+        assert_eq!(
+            vec![
+                (9, Value::Number(Number::I16(1))),
+                (11, Value::Number(Number::I16(-1)))
+            ],
+            values
+                .iter()
+                .map(|node| (node.offset, node.value.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        let state = node.value.field("state").unwrap();
+        assert_eq!((13, 3), (state.offset, state.len));
+        let error = state.value.field("error").unwrap();
+        assert_eq!(
+            (15, Value::Number(Number::U8(7))),
+            (error.offset, error.value.clone())
+        );
+
+        let mut output = Vec::new();
+        data_type.encode(&node.value, &mut output).unwrap();
+        assert_eq!(STATUS, output);
+    }
+
+    #[test]
+    fn invalid_values() {
+        let data_type = status_type();
+        let mut input = STATUS.to_vec();
+        input[14] = 0x01;
+        let error = data_type.decode(&input).unwrap_err();
+        assert_eq!(&Error::InvalidBool(0x07), error.root_cause());
+        assert_eq!(Some(15), error.offset());
+        assert_eq!("state.active", error.path().unwrap().to_string());
+
+        let error = data_type.decode(&STATUS[..12]).unwrap_err();
+        assert_eq!(
+            &Error::NotEnoughData { min: 5, actual: 4 },
+            error.root_cause()
+        );
+        assert_eq!("values", error.path().unwrap().to_string());
+
+        let Value::Struct(mut fields) = data_type.decode(STATUS).unwrap().value else {
+            panic!("status is not a struct");
+        };
+        let mut output = vec![0xAA];
+        let mut invalid = fields.clone();
+        invalid[3].node.value = Value::Union(1, None);
+        let error = data_type
+            .encode(&Value::Struct(invalid), &mut output)
+            .unwrap_err();
+        assert_eq!(&Error::InvalidValue("union"), error.root_cause());
+        assert_eq!(Some(14), error.offset());
+        assert_eq!("state", error.path().unwrap().to_string());
+        // on error, the output is left unchanged
+        assert_eq!(vec![0xAA], output);
+
+        let mut invalid = fields.clone();
+        invalid[0].node.value = Value::Number(Number::U32(1));
+        let error = data_type
+            .encode(&Value::Struct(invalid), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            &Error::InvalidValue("number of the data type"),
+            error.root_cause()
+        );
+        assert_eq!("id", error.path().unwrap().to_string());
+
+        let mut invalid = fields.clone();
+        invalid.push(Field {
+            name: "extra".into(),
+            node: fields[0].node.clone(),
+        });
+        let error = data_type
+            .encode(&Value::Struct(invalid), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            &Error::InvalidValue("unknown struct member"),
+            error.root_cause()
+        );
+        assert_eq!("extra", error.path().unwrap().to_string());
+
+        fields.remove(0);
+        let error = data_type
+            .encode(&Value::Struct(fields), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            &Error::InvalidValue("missing struct member"),
+            error.root_cause()
+        );
+        assert_eq!("id", error.path().unwrap().to_string());
+    }
+
+    #[test]
+    fn unknown_union_members() {
+        let data_type = status_type();
+        let mut input = STATUS.to_vec();
+        input[14] = 0x03;
+        let node = data_type.decode(&input).unwrap();
+        let state = node.value.field("state").unwrap();
+        assert_eq!((13, 3), (state.offset, state.len));
+        assert_eq!(Value::UnknownUnion(3, vec![0x07]), state.value);
+
+        let mut output = Vec::new();
+        data_type.encode(&node.value, &mut output).unwrap();
+        assert_eq!(input, output);
+
+        let DataType::Struct(struct_type) = &data_type else {
+            panic!("status is not a struct");
+        };
+        let state = &struct_type.members[3].data_type;
+        let error = state
+            .encode(&Value::UnknownUnion(0, vec![0x07]), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(Error::InvalidTypeSelector(0), error);
+    }
+
+    #[test]
+    fn alignment() {
+        let data_type = DataType::Struct(StructType {
+            members: vec![
+                member(
+                    "name",
+                    DataType::String(StringCodec::dynamic(Encoding::Utf8, LengthField::U8)),
+                ),
+                member(
+                    "inner",
+                    DataType::Struct(StructType {
+                        members: vec![
+                            member(
+                                "values",
+                                DataType::Array(
+                                    ArrayCodec::dynamic(LengthField::U8),
+                                    Box::new(DataType::Number(
+                                        NumberType::U8,
+                                        ByteOrder::BigEndian,
+                                    )),
+                                ),
+                            ),
+                            member("id", DataType::Number(NumberType::U8, ByteOrder::BigEndian)),
+                        ],
+                        length_field: LengthField::None,
+                        byte_order: ByteOrder::BigEndian,
+                        alignment: None,
+                    }),
+                ),
+                member(
+                    "fixed",
+                    DataType::Struct(StructType {
+                        members: vec![member(
+                            "text",
+                            DataType::String(StringCodec::dynamic(Encoding::Utf8, LengthField::U8)),
+                        )],
+                        length_field: LengthField::U8,
+                        byte_order: ByteOrder::BigEndian,
+                        alignment: Some(1),
+                    }),
+                ),
+            ],
+            length_field: LengthField::None,
+            byte_order: ByteOrder::BigEndian,
+            alignment: Some(4),
+        });
+        let payload = [
+            0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), name
+            0x00, 0x00, // padding
+            0x01, 0x02, // length(u8), values(u8)
+            0x00, 0x00, // padding (inherited alignment)
+            0x03, // id(u8)
+            0x06, 0x05, 0xEF, 0xBB, 0xBF, 0x62,
+            0x00, // length(u8), length(u8), text, alignment 1
+        ];
+        let node = data_type.decode(&payload).unwrap();
+        assert_eq!(payload.len(), node.len);
+        let inner = node.value.field("inner").unwrap();
+        assert_eq!((8, 5), (inner.offset, inner.len));
+        assert_eq!(
+            (12, Value::Number(Number::U8(3))),
+            inner
+                .value
+                .field("id")
+                .map(|id| (id.offset, id.value.clone()))
+                .unwrap()
+        );
+
+        let mut output = Vec::new();
+        data_type.encode(&node.value, &mut output).unwrap();
+        assert_eq!(payload.as_slice(), output);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let data_type = DataType::Array(
+            ArrayCodec::dynamic(LengthField::U8),
+            Box::new(DataType::Struct(StructType {
+                members: Vec::new(),
+                length_field: LengthField::None,
+                byte_order: ByteOrder::BigEndian,
+                alignment: None,
+            })),
+        );
+        let error = data_type.decode(&[0x02, 0x00, 0x00]).unwrap_err();
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+        assert_eq!(Some(1), error.offset());
+        assert_eq!("[0]", error.path().unwrap().to_string());
+        assert_eq!(Ok(1), data_type.decode(&[0x00]).map(|node| node.len));
+
+        let value = Value::Array(vec![Node {
+            offset: 1,
+            len: 0,
+            value: Value::Struct(Vec::new()),
+        }]);
+        let error = data_type.encode(&value, &mut Vec::new()).unwrap_err();
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+        assert_eq!("[0]", error.path().unwrap().to_string());
+    }
+
+    #[test]
+    fn payloads_of_messages() {
+        let message_id = MessageId::new(0x0103, 0x8005);
+        let mut types = PayloadTypes::new();
+        types.insert(message_id.clone(), MessageType::Notification, status_type());
+
+        let message = Message::notification(
+            message_id.clone(),
+            RequestId::new(0x0001, 0x0002),
+            0x01,
+            STATUS,
+        );
+        let node = types.decode(&message).unwrap();
+        assert_eq!(
+            Ok(STATUS.to_vec()),
+            types.encode(&message_id, MessageType::Notification, &node.value)
+        );
+
+        assert_eq!(
+            Err(Error::UnknownPayloadType {
+                service_id: 0x0103,
+                method_id: 0x8005,
+                message_type: MessageType::Response,
+            }),
+            types.encode(&message_id, MessageType::Response, &node.value)
+        );
+
+        let mut payload = STATUS.to_vec();
+        payload.push(0x00);
+        let message =
+            Message::notification(message_id, RequestId::new(0x0001, 0x0002), 0x01, &payload);
+        assert_eq!(
+            Err(Error::TrailingBytes {
+                offset: STATUS.len(),
+                len: 1
+            }),
+            types.decode(&message)
+        );
+    }
+}
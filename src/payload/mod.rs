@@ -0,0 +1,43 @@
+//! Encoding of SOME/IP payloads.
+//!
+//! Strings are encoded with the [`StringCodec`], dynamic length and
+//! multidimensional arrays with the [`ArrayCodec`], extensible structs
+//! with the [`TlvCodec`] and unions with the [`UnionCodec`].
+//! With the feature `serde`, types implementing `serde::Serialize` are
+//! serialized into the payload wire format with [`to_vec`] and types
+//! implementing `serde::Deserialize` are decoded with [`from_slice`].
+//! Types implementing [`SomeIpSerialize`] and [`SomeIpDeserialize`], derived
+//! with the feature `derive`, are encoded with `to_payload` and decoded with
+//! `from_payload`. Payloads of data types only known at runtime are decoded
+//! into trees of [`Value`]s with the [`PayloadTypes`] of the messages.
+
+mod array;
+mod codec;
+mod config;
+#[cfg(feature = "serde")]
+mod de;
+mod dynamic;
+#[cfg(feature = "serde")]
+mod ser;
+mod string;
+mod tlv;
+mod union;
+
+pub use array::ArrayCodec;
+#[doc(hidden)]
+pub use codec::__private;
+pub use codec::{FieldOptions, SomeIpDeserialize, SomeIpSerialize};
+pub use config::{ByteOrder, Config, LengthField};
+#[cfg(feature = "serde")]
+pub use de::{from_slice, from_slice_with_config, Deserializer};
+pub use dynamic::{
+    DataType, Field, Member, Node, Number, NumberType, PayloadTypes, StructType, UnionVariant,
+    Value,
+};
+#[cfg(feature = "serde")]
+pub use ser::{to_vec, to_vec_with_config, Compound, Serializer};
+#[cfg(feature = "derive")]
+pub use someip_messages_derive::{SomeIpDeserialize, SomeIpSerialize};
+pub use string::{Encoding, StringCodec, StringLength};
+pub use tlv::{TlvCodec, TlvMember, WireType};
+pub use union::{Union, UnionCodec, UnionMember};
@@ -0,0 +1,565 @@
+use super::{ArrayCodec, ByteOrder, Config, LengthField, UnionCodec};
+use crate::{Error, PathSegment::Index};
+use alloc::{string::ToString, vec::Vec};
+use core::fmt::Display;
+use serde::{ser, Serialize};
+
+/// Serialize a value into a SOME/IP payload using the default configuration.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    to_vec_with_config(value, Config::default())
+}
+
+/// Serialize a value into a SOME/IP payload using the given configuration.
+pub fn to_vec_with_config<T: Serialize + ?Sized>(
+    value: &T,
+    config: Config,
+) -> Result<Vec<u8>, Error> {
+    let mut serializer = Serializer::new(config);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_vec())
+}
+
+/// Serde serializer producing the SOME/IP payload wire format.
+///
+/// Structs and tuples are encoded as their members in order, sequences and
+/// byte arrays as dynamic length arrays and strings as dynamic length strings
+/// with byte order mark and terminating zero. Enums are encoded as unions
+/// whose type selector is the variant index plus one, as the type selector 0
+/// is reserved for the empty union. Options and maps have no
+/// representation and are rejected with [`Error::UnsupportedType`].
+/// Numbers, length and type fields are written in the byte order of the
+/// [`Config`], variable size members are followed by padding to its alignment.
+#[derive(Clone, Debug, Default)]
+pub struct Serializer {
+    output: Vec<u8>,
+    config: Config,
+}
+
+impl Serializer {
+    /// Creates a serializer using the given configuration.
+    pub fn new(config: Config) -> Self {
+        Self {
+            output: Vec::new(),
+            config,
+        }
+    }
+
+    /// The serialized payload
+    pub fn into_vec(self) -> Vec<u8> {
+        self.output
+    }
+
+    fn begin(&mut self, length_field: LengthField) -> Compound<'_> {
+        let position = length_field.reserve(&mut self.output);
+        Compound {
+            ser: self,
+            length_field,
+            position,
+            array: None,
+            len: 0,
+            union: None,
+        }
+    }
+
+    /// Pads the output to the alignment after a variable size member.
+    fn pad(&mut self) {
+        let len = self
+            .output
+            .len()
+            .next_multiple_of(self.config.alignment.max(1));
+        self.output.resize(len, 0x00);
+    }
+
+    fn begin_union(&mut self, variant_index: u32) -> Result<Compound<'_>, Error> {
+        let selector = variant_index
+            .checked_add(1)
+            .ok_or(Error::InvalidTypeSelector(variant_index))?;
+        let codec = self.config.union_codec();
+        let start = codec.begin(selector, &mut self.output)?;
+        let mut compound = self.begin(LengthField::None);
+        compound.union = Some((codec, start));
+        Ok(compound)
+    }
+}
+
+/// Serializes the members of sequences, tuples and structs.
+///
+/// The length field preceding the members is written at the end.
+#[derive(Debug)]
+pub struct Compound<'a> {
+    ser: &'a mut Serializer,
+    length_field: LengthField,
+    position: usize,
+    /// Codec of dynamic length arrays checking the number of elements
+    array: Option<ArrayCodec>,
+    len: usize,
+    /// Codec and start of the selected member of unions
+    union: Option<(UnionCodec, usize)>,
+}
+
+impl<'a> Compound<'a> {
+    fn end(self) -> Result<(), Error> {
+        if let Some(array) = self.array {
+            array.check_len(self.len)?;
+        }
+        if let Some((codec, start)) = self.union {
+            codec.finish(&mut self.ser.output, start)?;
+        } else {
+            self.length_field.finish(
+                &mut self.ser.output,
+                self.position,
+                self.ser.config.byte_order,
+            )?;
+        }
+
+        if self.array.is_some() || self.union.is_some() || self.length_field != LengthField::None {
+            self.ser.pad();
+        }
+        Ok(())
+    }
+}
+
+/// Serializes numbers in the byte order of the configuration.
+macro_rules! serialize_numbers {
+    ($($method:ident($ty:ty)),*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<(), Error> {
+                let bytes = match self.config.byte_order {
+                    ByteOrder::BigEndian => v.to_be_bytes(),
+                    ByteOrder::LittleEndian => v.to_le_bytes(),
+                };
+                self.output.extend_from_slice(&bytes);
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.output.push(v as u8);
+        Ok(())
+    }
+
+    serialize_numbers!(
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64)
+    );
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_str(v.encode_utf8(&mut [0u8; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.config.string_codec().encode(v, &mut self.output)?;
+        self.pad();
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        let array = self.config.array_codec();
+        let mut compound = self.begin(array.length_field);
+        compound.array = Some(array);
+        compound.len = v.len();
+        compound.ser.output.extend_from_slice(v);
+        compound.end()
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Err(Error::UnsupportedType("option"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), Error> {
+        Err(Error::UnsupportedType("option"))
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Error> {
+        self.begin_union(variant_index)?.end()
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let compound = self.begin_union(variant_index)?;
+        value.serialize(&mut *compound.ser)?;
+        compound.end()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        let array = self.config.array_codec();
+        let mut compound = self.begin(array.length_field);
+        compound.array = Some(array);
+        Ok(compound)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(LengthField::None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(LengthField::None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_union(variant_index)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::UnsupportedType("map"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(self.config.struct_length_field))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_union(variant_index)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let offset = self.ser.output.len();
+        value.serialize(&mut *self.ser)?;
+        // an element without bytes could not be deserialized again
+        if self.ser.output.len() == offset {
+            return Err(Error::ZeroSizedElement.context(offset, Index(self.len)));
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for Compound<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Position {
+        x: i16,
+        y: u32,
+        valid: bool,
+    }
+
+    #[derive(Serialize)]
+    struct Track<'a> {
+        id: u8,
+        name: &'a str,
+        speed: f32,
+        positions: Vec<Position>,
+        matrix: [[u8; 2]; 2],
+    }
+
+    #[test]
+    fn serialize_primitives() {
+        assert_eq!(vec![0x01], to_vec(&true).unwrap());
+        assert_eq!(vec![0xFF, 0xFE], to_vec(&-2i16).unwrap());
+        assert_eq!(
+            vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+            to_vec(&0x0102030405060708u64).unwrap()
+        );
+        assert_eq!(vec![0x3F, 0x80, 0x00, 0x00], to_vec(&1.0f32).unwrap());
+        assert_eq!(
+            vec![0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            to_vec(&-2.0f64).unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_struct() {
+        let track = Track {
+            id: 0x07,
+            name: "ab",
+            speed: 0.5,
+            positions: vec![
+                Position {
+                    x: 1,
+                    y: 2,
+                    valid: true,
+                },
+                Position {
+                    x: -1,
+                    y: 3,
+                    valid: false,
+                },
+            ],
+            matrix: [[0x01, 0x02], [0x03, 0x04]],
+        };
+
+        let expected: &[u8] = &[
+            0x07, // id(u8)
+            0x00, 0x00, 0x00, 0x06, // length(u32)
+            0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00, // bom, "ab", terminator
+            0x3F, 0x00, 0x00, 0x00, // speed(f32)
+            0x00, 0x00, 0x00, 0x0E, // length(u32)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, // x(i16), y(u32), valid(bool)
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x03, 0x00, // x(i16), y(u32), valid(bool)
+            0x01, 0x02, 0x03, 0x04, // matrix([[u8; 2]; 2])
+        ];
+        assert_eq!(expected, to_vec(&track).unwrap());
+    }
+
+    #[test]
+    fn serialize_zero_sized_elements() {
+        assert_eq!(Ok(vec![0x00, 0x00, 0x00, 0x00]), to_vec(&Vec::<()>::new()));
+
+        let error = to_vec(&vec![()]).unwrap_err();
+        assert_eq!(Some(4), error.offset());
+        assert_eq!("[0]", error.path().unwrap().to_string());
+        assert_eq!(&Error::ZeroSizedElement, error.root_cause());
+
+        // fixed size sequences of zero sized elements are fine
+        assert_eq!(Ok(vec![]), to_vec(&[(), ()]));
+    }
+
+    #[test]
+    fn serialize_with_length_fields() {
+        let config = Config {
+            string_length_field: LengthField::U8,
+            array_length_field: LengthField::U16,
+            struct_length_field: LengthField::U8,
+            ..Config::default()
+        };
+        let position = Position {
+            x: 1,
+            y: 2,
+            valid: true,
+        };
+
+        assert_eq!(
+            vec![0x07, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01],
+            to_vec_with_config(&position, config).unwrap()
+        );
+        assert_eq!(
+            vec![0x00, 0x03, 0x01, 0x02, 0x03],
+            to_vec_with_config(&vec![1u8, 2, 3], config).unwrap()
+        );
+        assert_eq!(
+            vec![0x05, 0xEF, 0xBB, 0xBF, 0x78, 0x00],
+            to_vec_with_config("x", config).unwrap()
+        );
+        assert_eq!(
+            Err(Error::LengthFieldOverflow { len: 260, max: 255 }),
+            to_vec_with_config(&"x".repeat(256), config)
+        );
+    }
+
+    #[test]
+    fn serialize_little_endian() {
+        let config = Config {
+            byte_order: ByteOrder::LittleEndian,
+            array_length_field: LengthField::U16,
+            struct_length_field: LengthField::U8,
+            ..Config::default()
+        };
+        let position = Position {
+            x: 1,
+            y: 2,
+            valid: true,
+        };
+
+        assert_eq!(
+            vec![0x07, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01],
+            to_vec_with_config(&position, config).unwrap()
+        );
+        assert_eq!(
+            vec![0x04, 0x00, 0x01, 0x02, 0x03, 0x04],
+            to_vec_with_config(&vec![0x0201u16, 0x0403], config).unwrap()
+        );
+        assert_eq!(
+            vec![0x00, 0x00, 0x80, 0x3F],
+            to_vec_with_config(&1.0f32, config).unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_with_alignment() {
+        let config = Config {
+            alignment: 4,
+            ..Config::default()
+        };
+        let track = Track {
+            id: 0x07,
+            name: "ab",
+            speed: 0.5,
+            positions: vec![Position {
+                x: 1,
+                y: 2,
+                valid: true,
+            }],
+            matrix: [[0x01, 0x02], [0x03, 0x04]],
+        };
+
+        let expected: &[u8] = &[
+            0x07, // id(u8)
+            0x00, 0x00, 0x00, 0x06, // length(u32)
+            0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00, 0x00, // bom, "ab", terminator, padding
+            0x3F, 0x00, 0x00, 0x00, // speed(f32)
+            0x00, 0x00, 0x00, 0x07, // length(u32)
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, // x(i16), y(u32), valid(bool)
+            0x00, // padding
+            0x01, 0x02, 0x03, 0x04, // matrix([[u8; 2]; 2])
+        ];
+        assert_eq!(expected, to_vec_with_config(&track, config).unwrap());
+    }
+
+    #[test]
+    fn serialize_unsupported() {
+        assert_eq!(Err(Error::UnsupportedType("option")), to_vec(&Some(1u8)));
+        assert_eq!(
+            Err(Error::UnsupportedType("map")),
+            to_vec(&std::collections::BTreeMap::<u8, u8>::new())
+        );
+    }
+}
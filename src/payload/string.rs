@@ -0,0 +1,356 @@
+use super::{ByteOrder, LengthField};
+use crate::Error;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::str;
+
+/// Character encoding of a string, announced by its byte order mark.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "schema",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Encoding {
+    /// UTF-8
+    #[default]
+    Utf8,
+    /// UTF-16 big endian
+    Utf16Be,
+    /// UTF-16 little endian
+    Utf16Le,
+}
+
+impl Encoding {
+    /// The byte order mark preceding strings in this encoding
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+        }
+    }
+
+    /// The encoding announced by the byte order mark at the start of the input
+    pub fn from_bom(input: &[u8]) -> Option<Encoding> {
+        [Encoding::Utf8, Encoding::Utf16Be, Encoding::Utf16Le]
+            .into_iter()
+            .find(|encoding| input.starts_with(encoding.bom()))
+    }
+
+    /// Size of the terminating zero in bytes
+    pub fn terminator_len(&self) -> usize {
+        match self {
+            Encoding::Utf8 => 1,
+            Encoding::Utf16Be | Encoding::Utf16Le => 2,
+        }
+    }
+}
+
+/// Length of a string.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum StringLength {
+    /// Fixed size in bytes, including byte order mark, terminator and padding
+    Fixed(usize),
+    /// Dynamic size preceded by a length field
+    Dynamic(LengthField),
+}
+
+/// Encoder and decoder of SOME/IP strings.
+///
+/// Strings start with the byte order mark of their encoding and end with a
+/// terminating zero. Fixed length strings and strings shorter than `min_size`
+/// are padded with zeros. UTF-16 strings are decoded in the byte order of
+/// their byte order mark.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct StringCodec {
+    /// Character encoding
+    pub encoding: Encoding,
+    /// Fixed or dynamic length
+    pub length: StringLength,
+    /// Minimum size in bytes, including byte order mark and terminator
+    pub min_size: usize,
+    /// Byte order of the length field
+    pub byte_order: ByteOrder,
+}
+
+impl StringCodec {
+    /// Codec of fixed length strings of `size` bytes.
+    pub fn fixed(encoding: Encoding, size: usize) -> Self {
+        Self {
+            encoding,
+            length: StringLength::Fixed(size),
+            min_size: size,
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Codec of dynamic length strings preceded by a length field.
+    pub fn dynamic(encoding: Encoding, length_field: LengthField) -> Self {
+        Self {
+            encoding,
+            length: StringLength::Dynamic(length_field),
+            min_size: 0,
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Appends the encoded string including its length field.
+    ///
+    /// On error, the output is left unchanged.
+    pub fn encode(&self, value: &str, output: &mut Vec<u8>) -> Result<(), Error> {
+        let position = output.len();
+        let result = self.encode_at(value, output);
+        if result.is_err() {
+            output.truncate(position);
+        }
+        result
+    }
+
+    fn encode_at(&self, value: &str, output: &mut Vec<u8>) -> Result<(), Error> {
+        let position = match self.length {
+            StringLength::Fixed(_) => output.len(),
+            StringLength::Dynamic(length_field) => length_field.reserve(output),
+        };
+        let start = output.len();
+
+        output.extend_from_slice(self.encoding.bom());
+        match self.encoding {
+            Encoding::Utf8 => output.extend_from_slice(value.as_bytes()),
+            Encoding::Utf16Be => value
+                .encode_utf16()
+                .for_each(|unit| output.extend_from_slice(&unit.to_be_bytes())),
+            Encoding::Utf16Le => value
+                .encode_utf16()
+                .for_each(|unit| output.extend_from_slice(&unit.to_le_bytes())),
+        }
+        output.resize(output.len() + self.encoding.terminator_len(), 0x00);
+
+        let len = output.len() - start;
+        match self.length {
+            StringLength::Fixed(size) if len > size => Err(Error::StringTooLong { len, max: size }),
+            StringLength::Fixed(size) => {
+                output.resize(start + size, 0x00);
+                Ok(())
+            }
+            StringLength::Dynamic(length_field) => {
+                output.resize(start + len.max(self.min_size), 0x00);
+                length_field.finish(output, position, self.byte_order)
+            }
+        }
+    }
+
+    /// Decodes a string from the start of the input.
+    ///
+    /// Returns the string and the number of bytes read, including the length field.
+    /// UTF-8 strings are borrowed from the input.
+    pub fn decode<'a>(&self, input: &'a [u8]) -> Result<(Cow<'a, str>, usize), Error> {
+        let (start, len) = match self.length {
+            StringLength::Fixed(size) => (0, size),
+            StringLength::Dynamic(length_field) => {
+                let len = length_field.decode(input, self.byte_order)?;
+                (length_field.size(), len.unwrap_or(input.len()))
+            }
+        };
+        let end = start + len;
+        if end > input.len() {
+            return Err(Error::NotEnoughData {
+                min: end,
+                actual: input.len(),
+            });
+        }
+
+        Ok((self.decode_content(&input[start..end])?, end))
+    }
+
+    /// Decodes the bytes of a string following its length field.
+    pub(crate) fn decode_content<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, Error> {
+        let min = self
+            .min_size
+            .max(self.encoding.bom().len() + self.encoding.terminator_len());
+        if bytes.len() < min {
+            return Err(Error::NotEnoughData {
+                min,
+                actual: bytes.len(),
+            });
+        }
+
+        let encoding = match (self.encoding, Encoding::from_bom(bytes)) {
+            (Encoding::Utf8, Some(Encoding::Utf8)) => Encoding::Utf8,
+            (Encoding::Utf16Be | Encoding::Utf16Le, Some(Encoding::Utf16Be)) => Encoding::Utf16Be,
+            (Encoding::Utf16Be | Encoding::Utf16Le, Some(Encoding::Utf16Le)) => Encoding::Utf16Le,
+            _ => return Err(Error::InvalidString("invalid byte order mark")),
+        };
+        let content = &bytes[encoding.bom().len()..];
+
+        if encoding == Encoding::Utf8 {
+            let len = content
+                .iter()
+                .position(|byte| *byte == 0x00)
+                .ok_or(Error::InvalidString("missing terminator"))?;
+            return str::from_utf8(&content[..len])
+                .map(Cow::Borrowed)
+                .map_err(|_| Error::InvalidString("invalid UTF-8"));
+        }
+
+        let units = content.chunks_exact(2).map(|unit| match encoding {
+            Encoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+            _ => u16::from_be_bytes([unit[0], unit[1]]),
+        });
+        let len = units
+            .clone()
+            .position(|unit| unit == 0x0000)
+            .ok_or(Error::InvalidString("missing terminator"))?;
+        char::decode_utf16(units.take(len))
+            .collect::<Result<String, _>>()
+            .map(Cow::Owned)
+            .map_err(|_| Error::InvalidString("invalid UTF-16"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dynamic_utf8_string() {
+        for (length_field, expected) in [
+            (
+                LengthField::U8,
+                &[0x06, 0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00][..],
+            ),
+            (
+                LengthField::U16,
+                &[0x00, 0x06, 0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00][..],
+            ),
+            (
+                LengthField::U32,
+                &[0x00, 0x00, 0x00, 0x06, 0xEF, 0xBB, 0xBF, 0x61, 0x62, 0x00][..],
+            ),
+        ] {
+            let codec = StringCodec::dynamic(Encoding::Utf8, length_field);
+            let mut output = Vec::new();
+            codec.encode("ab", &mut output).unwrap();
+            assert_eq!(expected, output);
+
+            let (value, len) = codec.decode(expected).unwrap();
+            assert!(matches!(value, Cow::Borrowed("ab")));
+            assert_eq!(expected.len(), len);
+        }
+    }
+
+    #[test]
+    fn dynamic_utf16_string() {
+        let big_endian: &[u8] = &[
+            0x08, // length(u8)
+            0xFE, 0xFF, // bom
+            0x00, 0x61, 0x00, 0xE4, // "aä"
+            0x00, 0x00, // terminator
+        ];
+        let little_endian: &[u8] = &[
+            0x08, // length(u8)
+            0xFF, 0xFE, // bom
+            0x61, 0x00, 0xE4, 0x00, // "aä"
+            0x00, 0x00, // terminator
+        ];
+
+        for (encoding, expected) in [
+            (Encoding::Utf16Be, big_endian),
+            (Encoding::Utf16Le, little_endian),
+        ] {
+            let codec = StringCodec::dynamic(encoding, LengthField::U8);
+            let mut output = Vec::new();
+            codec.encode("aä", &mut output).unwrap();
+            assert_eq!(expected, output);
+        }
+
+        // the byte order mark determines the byte order
+        let codec = StringCodec::dynamic(Encoding::Utf16Be, LengthField::U8);
+        assert_eq!(("aä".into(), 9), codec.decode(big_endian).unwrap());
+        assert_eq!(("aä".into(), 9), codec.decode(little_endian).unwrap());
+    }
+
+    #[test]
+    fn fixed_string() {
+        let codec = StringCodec::fixed(Encoding::Utf8, 8);
+        let expected: &[u8] = &[
+            0xEF, 0xBB, 0xBF, // bom
+            0x61, 0x62, // "ab"
+            0x00, // terminator
+            0x00, 0x00, // padding
+        ];
+
+        let mut output = Vec::new();
+        codec.encode("ab", &mut output).unwrap();
+        assert_eq!(expected, output);
+        assert_eq!(("ab".into(), 8), codec.decode(expected).unwrap());
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 8, actual: 7 }),
+            codec.decode(&expected[..7])
+        );
+        assert_eq!(
+            Err(Error::StringTooLong { len: 9, max: 8 }),
+            codec.encode("abcde", &mut output)
+        );
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn min_size() {
+        let codec = StringCodec {
+            min_size: 6,
+            ..StringCodec::dynamic(Encoding::Utf8, LengthField::U8)
+        };
+
+        let mut output = Vec::new();
+        codec.encode("", &mut output).unwrap();
+        assert_eq!(vec![0x06, 0xEF, 0xBB, 0xBF, 0x00, 0x00, 0x00], output);
+        assert_eq!(("".into(), 7), codec.decode(&output).unwrap());
+
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 6, actual: 4 }),
+            codec.decode(&[0x04, 0xEF, 0xBB, 0xBF, 0x00])
+        );
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 7, actual: 5 }),
+            codec.decode(&[0x06, 0xEF, 0xBB, 0xBF, 0x00])
+        );
+
+        // the output is left unchanged if the length field overflows
+        assert_eq!(
+            Err(Error::LengthFieldOverflow { len: 256, max: 255 }),
+            codec.encode(&"a".repeat(252), &mut output)
+        );
+        assert_eq!(vec![0x06, 0xEF, 0xBB, 0xBF, 0x00, 0x00, 0x00], output);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        let utf8 = StringCodec::dynamic(Encoding::Utf8, LengthField::U8);
+        let utf16 = StringCodec::dynamic(Encoding::Utf16Le, LengthField::U8);
+
+        assert_eq!(
+            Err(Error::InvalidString("invalid byte order mark")),
+            utf8.decode(&[0x04, 0xFE, 0xFF, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("invalid byte order mark")),
+            utf16.decode(&[0x04, 0xEF, 0xBB, 0xBF, 0x00])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("missing terminator")),
+            utf8.decode(&[0x04, 0xEF, 0xBB, 0xBF, 0x61])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("missing terminator")),
+            utf16.decode(&[0x05, 0xFF, 0xFE, 0x61, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("invalid UTF-8")),
+            utf8.decode(&[0x05, 0xEF, 0xBB, 0xBF, 0xFF, 0x00])
+        );
+        assert_eq!(
+            Err(Error::InvalidString("invalid UTF-16")),
+            utf16.decode(&[0x06, 0xFF, 0xFE, 0x00, 0xD8, 0x00, 0x00])
+        );
+    }
+}
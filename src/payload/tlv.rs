@@ -0,0 +1,430 @@
+use super::{ByteOrder, LengthField};
+use crate::Error;
+use alloc::vec::Vec;
+
+/// Largest data id, the tag holds 12 bits of it
+const MAX_DATA_ID: u16 = 0x0FFF;
+
+/// Wire type of a TLV member, bits 12 to 14 of its tag.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum WireType {
+    /// 8 bit base data type
+    Fixed8,
+    /// 16 bit base data type
+    Fixed16,
+    /// 32 bit base data type
+    Fixed32,
+    /// 64 bit base data type
+    Fixed64,
+    /// Length delimited with the length field size of the data definition
+    Static,
+    /// Length delimited with an 8 bit length field
+    Dynamic8,
+    /// Length delimited with a 16 bit length field
+    Dynamic16,
+    /// Length delimited with a 32 bit length field
+    Dynamic32,
+}
+
+impl WireType {
+    fn from_tag(tag: u16) -> WireType {
+        match (tag >> 12) & 0x07 {
+            0 => WireType::Fixed8,
+            1 => WireType::Fixed16,
+            2 => WireType::Fixed32,
+            3 => WireType::Fixed64,
+            4 => WireType::Static,
+            5 => WireType::Dynamic8,
+            6 => WireType::Dynamic16,
+            _ => WireType::Dynamic32,
+        }
+    }
+
+    /// Size of the value of base data types
+    pub fn fixed_size(&self) -> Option<usize> {
+        match self {
+            WireType::Fixed8 => Some(1),
+            WireType::Fixed16 => Some(2),
+            WireType::Fixed32 => Some(4),
+            WireType::Fixed64 => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Length field of length delimited values, given the length field of static ones
+    fn length_field(&self, static_length_field: LengthField) -> LengthField {
+        match self {
+            WireType::Static => static_length_field,
+            WireType::Dynamic8 => LengthField::U8,
+            WireType::Dynamic16 => LengthField::U16,
+            WireType::Dynamic32 => LengthField::U32,
+            _ => LengthField::None,
+        }
+    }
+}
+
+/// Transforms a WireType to a u8 representing the type.
+impl From<WireType> for u8 {
+    fn from(wire_type: WireType) -> u8 {
+        match wire_type {
+            WireType::Fixed8 => 0,
+            WireType::Fixed16 => 1,
+            WireType::Fixed32 => 2,
+            WireType::Fixed64 => 3,
+            WireType::Static => 4,
+            WireType::Dynamic8 => 5,
+            WireType::Dynamic16 => 6,
+            WireType::Dynamic32 => 7,
+        }
+    }
+}
+
+/// Member of a TLV encoded struct.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TlvMember<'a> {
+    /// Data id identifying the member, at most 0x0FFF
+    pub data_id: u16,
+    /// Wire type of the value
+    pub wire_type: WireType,
+    /// Encoded value, without tag and length field
+    pub value: &'a [u8],
+}
+
+/// Encoder and decoder of TLV encoded structs.
+///
+/// Each member is preceded by a tag holding its wire type and data id, so
+/// members can be added or removed without breaking compatibility.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TlvCodec {
+    /// Length field of the struct
+    pub length_field: LengthField,
+    /// Length field of members with the [`WireType::Static`] wire type
+    pub static_length_field: LengthField,
+    /// Byte order of tags and length fields
+    pub byte_order: ByteOrder,
+}
+
+impl Default for TlvCodec {
+    fn default() -> Self {
+        Self {
+            length_field: LengthField::U32,
+            static_length_field: LengthField::U32,
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+}
+
+impl TlvCodec {
+    /// Appends the encoded struct.
+    ///
+    /// On error, the output is left unchanged.
+    pub fn encode(&self, members: &[TlvMember], output: &mut Vec<u8>) -> Result<(), Error> {
+        let position = output.len();
+        let result = self.encode_members(members, output);
+        if result.is_err() {
+            output.truncate(position);
+        }
+        result
+    }
+
+    fn encode_members(&self, members: &[TlvMember], output: &mut Vec<u8>) -> Result<(), Error> {
+        let position = self.length_field.reserve(output);
+        for (index, member) in members.iter().enumerate() {
+            if member.data_id > MAX_DATA_ID {
+                return Err(Error::InvalidDataId(member.data_id));
+            }
+            if members[..index]
+                .iter()
+                .any(|other| other.data_id == member.data_id)
+            {
+                return Err(Error::DuplicateDataId(member.data_id));
+            }
+
+            let tag = (u16::from(u8::from(member.wire_type)) << 12) | member.data_id;
+            let mut bytes = [0u8; 2];
+            self.byte_order.write(tag as usize, &mut bytes);
+            output.extend_from_slice(&bytes);
+            match member.wire_type.fixed_size() {
+                Some(size) if size != member.value.len() => {
+                    return Err(Error::InvalidTlvValue {
+                        data_id: member.data_id,
+                        len: member.value.len(),
+                    })
+                }
+                Some(_) => output.extend_from_slice(member.value),
+                None => {
+                    let length_field = self.length_field_of(member.data_id, member.wire_type)?;
+                    let value_position = length_field.reserve(output);
+                    output.extend_from_slice(member.value);
+                    length_field.finish(output, value_position, self.byte_order)?;
+                }
+            }
+        }
+        self.length_field.finish(output, position, self.byte_order)
+    }
+
+    /// Decodes a struct from the start of the input.
+    ///
+    /// Members whose data id is not in `data_ids` are skipped. Returns the members
+    /// in the order of the input and the number of bytes read, including the length field.
+    pub fn decode<'a>(
+        &self,
+        input: &'a [u8],
+        data_ids: &[u16],
+    ) -> Result<(Vec<TlvMember<'a>>, usize), Error> {
+        let mut offset = self.length_field.size();
+        let end = self.length_field.delimit(input, self.byte_order)?.end;
+
+        let mut members = Vec::new();
+        let mut seen = Vec::new();
+        while offset < end {
+            let tag = self.byte_order.read(read(input, offset, 2, end)?) as u16;
+            let wire_type = WireType::from_tag(tag);
+            let data_id = tag & MAX_DATA_ID;
+            offset += 2;
+
+            if seen.contains(&data_id) {
+                return Err(Error::DuplicateDataId(data_id));
+            }
+            seen.push(data_id);
+
+            let len = match wire_type.fixed_size() {
+                Some(size) => size,
+                None => {
+                    let length_field = self.length_field_of(data_id, wire_type)?;
+                    let len = length_field.decode(
+                        read(input, offset, length_field.size(), end)?,
+                        self.byte_order,
+                    )?;
+                    offset += length_field.size();
+                    len.unwrap_or_default()
+                }
+            };
+            let value = read(input, offset, len, end)?;
+            offset += len;
+
+            if data_ids.contains(&data_id) {
+                members.push(TlvMember {
+                    data_id,
+                    wire_type,
+                    value,
+                });
+            }
+        }
+
+        Ok((members, end))
+    }
+
+    /// Length field of a length delimited member.
+    ///
+    /// A static member without static length field cannot be delimited.
+    fn length_field_of(&self, data_id: u16, wire_type: WireType) -> Result<LengthField, Error> {
+        match wire_type.length_field(self.static_length_field) {
+            LengthField::None => Err(Error::MissingStaticLengthField(data_id)),
+            length_field => Ok(length_field),
+        }
+    }
+}
+
+/// Reads `len` bytes at `offset` of a struct ending at `end`.
+fn read(input: &[u8], offset: usize, len: usize, end: usize) -> Result<&[u8], Error> {
+    match offset.checked_add(len) {
+        Some(value_end) if value_end <= end => Ok(&input[offset..value_end]),
+        value_end => Err(Error::NotEnoughData {
+            min: value_end.unwrap_or(usize::MAX),
+            actual: end,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STRUCT: &[u8] = &[
+        0x00, 0x00, 0x00, 0x2C, // length(u32)
+        0x00, 0x01, 0x11, // tag(fixed8, 1), value(u8)
+        0x10, 0x02, 0x22, 0x22, // tag(fixed16, 2), value(u16)
+        0x20, 0x03, 0x33, 0x33, 0x33, 0x33, // tag(fixed32, 3), value(u32)
+        0x30, 0x04, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44,
+        0x44, // tag(fixed64, 4), value(u64)
+        0x40, 0x05, 0x00, 0x01, 0x55, // tag(static, 5), length(u16), value
+        0x50, 0x06, 0x01, 0x66, // tag(dynamic8, 6), length(u8), value
+        0x60, 0x07, 0x00, 0x01, 0x77, // tag(dynamic16, 7), length(u16), value
+        0x7F, 0xFF, 0x00, 0x00, 0x00, 0x01, 0x88, // tag(dynamic32, 0xFFF), length(u32), value
+    ];
+
+    fn members() -> Vec<TlvMember<'static>> {
+        vec![
+            TlvMember {
+                data_id: 1,
+                wire_type: WireType::Fixed8,
+                value: &[0x11],
+            },
+            TlvMember {
+                data_id: 2,
+                wire_type: WireType::Fixed16,
+                value: &[0x22, 0x22],
+            },
+            TlvMember {
+                data_id: 3,
+                wire_type: WireType::Fixed32,
+                value: &[0x33; 4],
+            },
+            TlvMember {
+                data_id: 4,
+                wire_type: WireType::Fixed64,
+                value: &[0x44; 8],
+            },
+            TlvMember {
+                data_id: 5,
+                wire_type: WireType::Static,
+                value: &[0x55],
+            },
+            TlvMember {
+                data_id: 6,
+                wire_type: WireType::Dynamic8,
+                value: &[0x66],
+            },
+            TlvMember {
+                data_id: 7,
+                wire_type: WireType::Dynamic16,
+                value: &[0x77],
+            },
+            TlvMember {
+                data_id: 0x0FFF,
+                wire_type: WireType::Dynamic32,
+                value: &[0x88],
+            },
+        ]
+    }
+
+    fn codec() -> TlvCodec {
+        TlvCodec {
+            static_length_field: LengthField::U16,
+            ..TlvCodec::default()
+        }
+    }
+
+    #[test]
+    fn encode_all_wire_types() {
+        let mut output = Vec::new();
+        codec().encode(&members(), &mut output).unwrap();
+        assert_eq!(STRUCT, output);
+    }
+
+    #[test]
+    fn decode_all_wire_types() {
+        let data_ids: Vec<u16> = members().iter().map(|member| member.data_id).collect();
+        assert_eq!(
+            (members(), STRUCT.len()),
+            codec().decode(STRUCT, &data_ids).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_skips_unknown_members() {
+        let (members, len) = codec().decode(STRUCT, &[2, 6]).unwrap();
+        assert_eq!(STRUCT.len(), len);
+        assert_eq!(
+            vec![
+                TlvMember {
+                    data_id: 2,
+                    wire_type: WireType::Fixed16,
+                    value: &[0x22, 0x22],
+                },
+                TlvMember {
+                    data_id: 6,
+                    wire_type: WireType::Dynamic8,
+                    value: &[0x66],
+                },
+            ],
+            members
+        );
+    }
+
+    #[test]
+    fn duplicate_data_ids() {
+        let input = [
+            0x07, // length(u8)
+            0x00, 0x01, 0x11, // tag(fixed8, 1), value(u8)
+            0x50, 0x01, 0x01, 0x22, // tag(dynamic8, 1), length(u8), value
+        ];
+        let codec = TlvCodec {
+            length_field: LengthField::U8,
+            ..TlvCodec::default()
+        };
+        assert_eq!(
+            Err(Error::DuplicateDataId(1)),
+            codec.decode(&input[..], &[1])
+        );
+        assert_eq!(
+            Err(Error::DuplicateDataId(1)),
+            codec.decode(&input[..], &[])
+        );
+
+        let mut members = members();
+        members[1].data_id = 1;
+        assert_eq!(
+            Err(Error::DuplicateDataId(1)),
+            codec.encode(&members, &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn invalid_members() {
+        let mut members = members();
+        members[0].data_id = 0x1000;
+        assert_eq!(
+            Err(Error::InvalidDataId(0x1000)),
+            codec().encode(&members, &mut Vec::new())
+        );
+
+        let members = [TlvMember {
+            data_id: 1,
+            wire_type: WireType::Fixed16,
+            value: &[0x01],
+        }];
+        let mut output = vec![0xAA];
+        assert_eq!(
+            Err(Error::InvalidTlvValue { data_id: 1, len: 1 }),
+            codec().encode(&members, &mut output)
+        );
+        assert_eq!(vec![0xAA], output);
+
+        let codec_without_static = TlvCodec {
+            static_length_field: LengthField::None,
+            ..TlvCodec::default()
+        };
+        assert_eq!(
+            Err(Error::MissingStaticLengthField(5)),
+            codec_without_static.encode(&self::members(), &mut output)
+        );
+        assert_eq!(vec![0xAA], output);
+        assert_eq!(
+            Err(Error::MissingStaticLengthField(5)),
+            codec_without_static.decode(&[0x00, 0x00, 0x00, 0x03, 0x40, 0x05, 0x55], &[])
+        );
+
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 48, actual: 9 }),
+            codec().decode(&STRUCT[..9], &[])
+        );
+        // truncated value of a fixed size member
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 8, actual: 7 }),
+            TlvCodec::default().decode(&[0x00, 0x00, 0x00, 0x03, 0x10, 0x02, 0x22], &[])
+        );
+        // length field exceeding the struct
+        assert_eq!(
+            Err(Error::NotEnoughData {
+                min: 0x1_0009,
+                actual: 10
+            }),
+            TlvCodec::default().decode(
+                &[0x00, 0x00, 0x00, 0x06, 0x70, 0x01, 0x00, 0x00, 0xFF, 0xFF],
+                &[]
+            )
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use super::{ByteOrder, LengthField};
+use crate::Error;
+use alloc::vec::Vec;
+
+/// A decoded SOME/IP union.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Union<'a, T> {
+    /// No member selected, type selector 0
+    Empty,
+    /// A known member
+    Member(T),
+    /// A member with an unknown type selector
+    Unknown {
+        /// The type selector
+        selector: u32,
+        /// The encoded member including padding
+        data: &'a [u8],
+    },
+}
+
+/// Rust type, usually an enum, mapped to the members of a SOME/IP union.
+pub trait UnionMember<'a>: Sized {
+    /// The type selector of the member, 0 is reserved for the empty union
+    fn selector(&self) -> u32;
+
+    /// Appends the encoded member.
+    fn encode(&self, output: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// Decodes the member of a type selector, `None` for unknown selectors.
+    ///
+    /// The data includes the padding of the member. Returns the member and the
+    /// number of bytes read, which unions without length field need to find its end.
+    fn decode(selector: u32, data: &'a [u8]) -> Option<Result<(Self, usize), Error>>;
+}
+
+/// Encoder and decoder of SOME/IP unions.
+///
+/// A union is encoded as a length field, a type field holding the type
+/// selector and the member padded with zeros to a multiple of `alignment`
+/// bytes. The length field holds the size of the padded member.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UnionCodec {
+    /// Length field preceding the type field
+    pub length_field: LengthField,
+    /// Size of the type field, must not be [`LengthField::None`]
+    pub type_field: LengthField,
+    /// Alignment of the padded member in bytes
+    pub alignment: usize,
+    /// Byte order of length and type field
+    pub byte_order: ByteOrder,
+}
+
+impl Default for UnionCodec {
+    fn default() -> Self {
+        Self {
+            length_field: LengthField::U32,
+            type_field: LengthField::U32,
+            alignment: 1,
+            byte_order: ByteOrder::BigEndian,
+        }
+    }
+}
+
+impl UnionCodec {
+    /// Appends the encoded union.
+    ///
+    /// On error, the output is left unchanged.
+    pub fn encode<'a, T: UnionMember<'a>>(
+        &self,
+        value: &Union<'a, T>,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let position = output.len();
+        let result = self.encode_at(value, output);
+        if result.is_err() {
+            output.truncate(position);
+        }
+        result
+    }
+
+    fn encode_at<'a, T: UnionMember<'a>>(
+        &self,
+        value: &Union<'a, T>,
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        match value {
+            Union::Empty => {
+                let start = self.begin(0, output)?;
+                self.finish(output, start)
+            }
+            Union::Member(member) => {
+                if member.selector() == 0 {
+                    return Err(Error::InvalidTypeSelector(0));
+                }
+                let start = self.begin(member.selector(), output)?;
+                member.encode(output)?;
+                self.finish(output, start)
+            }
+            Union::Unknown { selector, data } => {
+                if *selector == 0 && !data.is_empty() {
+                    return Err(Error::InvalidTypeSelector(0));
+                }
+                let start = self.begin(*selector, output)?;
+                output.extend_from_slice(data);
+                self.finish(output, start)
+            }
+        }
+    }
+
+    /// Decodes a union from the start of the input.
+    ///
+    /// Returns the union and the number of bytes read, including length and type field.
+    /// Members with unknown type selectors are returned as [`Union::Unknown`], without
+    /// length field they extend to the end of the input.
+    pub fn decode<'a, T: UnionMember<'a>>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<(Union<'a, T>, usize), Error> {
+        self.decode_member(input, |selector, data| match selector {
+            0 => Ok((Union::Empty, 0)),
+            selector => match T::decode(selector, data) {
+                Some(member) => member.map(|(member, len)| (Union::Member(member), len)),
+                None => Ok((Union::Unknown { selector, data }, data.len())),
+            },
+        })
+    }
+
+    /// Decodes length and type field at the start of the input and the member with `decode`.
+    ///
+    /// `decode` gets the type selector and the member including padding, without length
+    /// field extending to the end of the input, and returns the decoded member and the
+    /// number of bytes it read. Returns the member and the number of bytes read of the
+    /// union, including length and type field and the padding of the member.
+    pub(crate) fn decode_member<'a, T>(
+        &self,
+        input: &'a [u8],
+        decode: impl FnOnce(u32, &'a [u8]) -> Result<(T, usize), Error>,
+    ) -> Result<(T, usize), Error> {
+        let start = self.length_field.size() + self.type_field.size();
+        if input.len() < start {
+            return Err(Error::NotEnoughData {
+                min: start,
+                actual: input.len(),
+            });
+        }
+        let len = self.length_field.decode(input, self.byte_order)?;
+        let selector = self.decode_selector(&input[self.length_field.size()..])?;
+        let end = match len {
+            Some(len) => start + len,
+            None => input.len(),
+        };
+        if end > input.len() {
+            return Err(Error::NotEnoughData {
+                min: end,
+                actual: input.len(),
+            });
+        }
+
+        let data = &input[start..end];
+        if selector == 0 && self.length_field != LengthField::None && !data.is_empty() {
+            return Err(Error::InvalidTypeSelector(0));
+        }
+        let (value, len) = decode(selector, data)?;
+        let len = match self.length_field {
+            LengthField::None => len.next_multiple_of(self.alignment.max(1)).min(data.len()),
+            _ => data.len(),
+        };
+        Ok((value, start + len))
+    }
+
+    /// Appends length and type field and returns the start of the member.
+    pub(crate) fn begin(&self, selector: u32, output: &mut Vec<u8>) -> Result<usize, Error> {
+        if self.type_field == LengthField::None || selector as usize > self.type_field.max() {
+            return Err(Error::InvalidTypeSelector(selector));
+        }
+        self.length_field.reserve(output);
+        let position = self.type_field.reserve(output);
+        self.type_field
+            .write(output, position, selector as usize, self.byte_order)?;
+        Ok(output.len())
+    }
+
+    /// Pads the member starting at `start` and writes the length field.
+    pub(crate) fn finish(&self, output: &mut Vec<u8>, start: usize) -> Result<(), Error> {
+        let len = output
+            .len()
+            .saturating_sub(start)
+            .next_multiple_of(self.alignment.max(1));
+        output.resize(start + len, 0x00);
+        let position = start - self.type_field.size() - self.length_field.size();
+        self.length_field
+            .write(output, position, len, self.byte_order)
+    }
+
+    /// Reads the type selector at the start of the input.
+    pub(crate) fn decode_selector(&self, input: &[u8]) -> Result<u32, Error> {
+        match self.type_field.decode(input, self.byte_order)? {
+            Some(selector) => Ok(selector as u32),
+            None => Err(Error::InvalidTypeSelector(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Value<'a> {
+        Number(u16),
+        Text(&'a [u8]),
+    }
+
+    impl<'a> UnionMember<'a> for Value<'a> {
+        fn selector(&self) -> u32 {
+            match self {
+                Value::Number(_) => 1,
+                Value::Text(_) => 2,
+            }
+        }
+
+        fn encode(&self, output: &mut Vec<u8>) -> Result<(), Error> {
+            match self {
+                Value::Number(number) => output.extend_from_slice(&number.to_be_bytes()),
+                Value::Text(text) => output.extend_from_slice(text),
+            }
+            Ok(())
+        }
+
+        fn decode(selector: u32, data: &'a [u8]) -> Option<Result<(Self, usize), Error>> {
+            match selector {
+                1 => Some(match data {
+                    [a, b, ..] => Ok((Value::Number(u16::from_be_bytes([*a, *b])), 2)),
+                    _ => Err(Error::NotEnoughData {
+                        min: 2,
+                        actual: data.len(),
+                    }),
+                }),
+                2 => Some(Ok((Value::Text(data), data.len()))),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn encode_and_decode_member() {
+        let expected: &[u8] = &[
+            0x00, 0x00, 0x00, 0x02, // length(u32)
+            0x00, 0x00, 0x00, 0x01, // type(u32)
+            0x12, 0x34, // member(u16)
+        ];
+        let value = Union::Member(Value::Number(0x1234));
+
+        let mut output = Vec::new();
+        UnionCodec::default().encode(&value, &mut output).unwrap();
+        assert_eq!(expected, output);
+        assert_eq!(
+            (value, expected.len()),
+            UnionCodec::default().decode(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn configured_fields_and_padding() {
+        let codec = UnionCodec {
+            length_field: LengthField::U8,
+            type_field: LengthField::U16,
+            alignment: 4,
+            ..UnionCodec::default()
+        };
+        let expected: &[u8] = &[
+            0x04, // length(u8)
+            0x00, 0x02, // type(u16)
+            0x61, 0x62, 0x00, 0x00, // member, padding
+        ];
+
+        let mut output = Vec::new();
+        codec
+            .encode(&Union::Member(Value::Text(b"ab")), &mut output)
+            .unwrap();
+        assert_eq!(expected, output);
+        assert_eq!(
+            (Union::Member(Value::Text(&[0x61, 0x62, 0x00, 0x00])), 7),
+            codec.decode(expected).unwrap()
+        );
+
+        let unknown: Union<Value> = Union::Unknown {
+            selector: 0x10000,
+            data: &[],
+        };
+        assert_eq!(
+            Err(Error::InvalidTypeSelector(0x10000)),
+            codec.encode(&unknown, &mut output)
+        );
+
+        // members exceeding the length field leave the output unchanged
+        let text = Union::Member(Value::Text(&[0x61; 0x100]));
+        assert!(codec.encode(&text, &mut output).is_err());
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn empty_and_unknown_members() {
+        let codec = UnionCodec {
+            length_field: LengthField::U8,
+            type_field: LengthField::U8,
+            ..UnionCodec::default()
+        };
+
+        let mut output = Vec::new();
+        codec
+            .encode(&Union::<'_, Value>::Empty, &mut output)
+            .unwrap();
+        assert_eq!(vec![0x00, 0x00], output);
+        assert_eq!(
+            (Union::<'_, Value>::Empty, 2),
+            codec.decode(&output).unwrap()
+        );
+
+        let input = [0x02, 0x07, 0xAA, 0xBB, 0xFF];
+        let (value, len) = codec.decode::<Value>(&input).unwrap();
+        assert_eq!(
+            Union::Unknown {
+                selector: 7,
+                data: &[0xAA, 0xBB]
+            },
+            value
+        );
+        assert_eq!(4, len);
+
+        let mut output = Vec::new();
+        codec.encode(&value, &mut output).unwrap();
+        assert_eq!(&input[..4], output);
+    }
+
+    #[test]
+    fn union_without_length_field() {
+        let codec = UnionCodec {
+            length_field: LengthField::None,
+            type_field: LengthField::U8,
+            alignment: 4,
+            ..UnionCodec::default()
+        };
+        let input: &[u8] = &[
+            0x01, // type(u8)
+            0x12, 0x34, 0x00, 0x00, // member(u16), padding
+            0xFF, // next member
+        ];
+
+        let mut output = Vec::new();
+        codec
+            .encode(&Union::Member(Value::Number(0x1234)), &mut output)
+            .unwrap();
+        assert_eq!(&input[..5], output);
+        assert_eq!(
+            (Union::Member(Value::Number(0x1234)), 5),
+            codec.decode(input).unwrap()
+        );
+        // missing padding at the end of the input is tolerated
+        assert_eq!(
+            (Union::Member(Value::Number(0x1234)), 3),
+            codec.decode(&input[..3]).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_unions() {
+        let codec = UnionCodec::default();
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 9, actual: 8 }),
+            codec.decode::<Value>(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02])
+        );
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 8, actual: 6 }),
+            codec.decode::<Value>(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 2, actual: 1 }),
+            codec.decode::<Value>(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x12])
+        );
+
+        // the empty union has no member
+        let unknown: Union<Value> = Union::Unknown {
+            selector: 0,
+            data: &[0x01],
+        };
+        assert_eq!(
+            Err(Error::InvalidTypeSelector(0)),
+            codec.encode(&unknown, &mut Vec::new())
+        );
+        assert_eq!(
+            Err(Error::InvalidTypeSelector(0)),
+            codec.decode::<Value>(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01])
+        );
+    }
+}
@@ -0,0 +1,1137 @@
+//! Schema format of services and the data types of their payloads.
+//!
+//! Schemas are authored in JSON or YAML and validated when loaded with
+//! [`Schema::from_json`] or [`Schema::from_yaml`]. [`Schema::payload_types`]
+//! resolves the data types of all payloads for the runtime decoder
+//! [`PayloadTypes`].
+//!
+//! ```
+//! use someip_messages::{schema::Schema, MessageId, MessageType};
+//!
+//! let schema = Schema::from_yaml(
+//!     r#"
+//! types:
+//!   - name: Position
+//!     kind: struct
+//!     members:
+//!       - { name: x, type: int16 }
+//!       - { name: y, type: int16 }
+//!   - name: Positions
+//!     kind: array
+//!     element: Position
+//!     length_field: 16
+//! services:
+//!   - id: 0x0103
+//!     name: Tracking
+//!     methods:
+//!       - id: 0x0001
+//!         name: reset
+//!         fire_and_forget: true
+//!         request: [{ name: track, type: uint8 }]
+//!     events:
+//!       - { id: 0x8001, name: positions, type: Positions }
+//!     fields:
+//!       - { name: speed, type: float32, getter: 0x0002, notifier: 0x8002 }
+//!     eventgroups:
+//!       - { id: 1, name: tracking, events: [0x8001, 0x8002] }
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! let service = schema.service(0x0103).unwrap();
+//! assert_eq!("tracking", service.eventgroup(1).unwrap().name);
+//!
+//! let types = schema.payload_types().unwrap();
+//! let message_id = MessageId::new(0x0103, 0x8001);
+//! assert!(types.get(&message_id, MessageType::Notification).is_some());
+//! ```
+//!
+//! # Format
+//!
+//! A schema holds the `byte_order` of its payloads (`big_endian` by default or
+//! `little_endian`), the `alignment` of struct members in bytes, 1 by default,
+//! named data `types` and `services`. Strings, arrays without fixed length,
+//! unions and structs with a length field are padded with zeros to a multiple
+//! of the alignment from the start of the payload.
+//!
+//! Members, parameters and elements refer to a type by name, either a type of
+//! the schema or one of the predefined types `bool`, `uint8`, `uint16`,
+//! `uint32`, `uint64`, `int8`, `int16`, `int32`, `int64`, `float32`, `float64`
+//! and `string`, a UTF-8 string with a 32 bit length field. Types are defined
+//! with a `name` and a `kind`:
+//! - `struct`: `members` with `name` and `type`, a `length_field` of 0, 8, 16
+//!   or 32 bits, 0 by default, and the `alignment` of the members, the
+//!   alignment of the schema by default
+//! - `string`: `encoding` (`utf8`, `utf16be` or `utf16le`) and either a
+//!   `length_field`, 32 bits by default, or a `fixed_size` in bytes holding at
+//!   least byte order mark and terminator, even for UTF-16
+//! - `array`: `element` type and either a `length_field`, 32 bits by default,
+//!   with optional `min_elements` and `max_elements` or a fixed `length`.
+//!   Multidimensional arrays are arrays of arrays. The elements of arrays
+//!   without a fixed length must not be encodable without any bytes, like
+//!   structs without members.
+//! - `union`: `members` with `selector`, `name` and `type`, a `length_field`
+//!   and a `type_field`, 32 bits by default, and the `alignment` of the members
+//!
+//! Services have an `id`, a `name` and
+//! - `methods` with `id`, `name`, `request` and `response` parameters,
+//!   methods without response are `fire_and_forget`
+//! - `events` with `id`, `name` and `type`
+//! - `fields` with `name`, `type` and the method ids of `getter`, `setter`
+//!   and `notifier`
+//! - `eventgroups` with `id`, `name` and the ids of their `events`,
+//!   including the notifiers of fields
+
+use crate::{
+    payload::{
+        self, ArrayCodec, ByteOrder, DataType, Encoding, LengthField, NumberType, PayloadTypes,
+        StringCodec, StructType, UnionCodec, UnionVariant,
+    },
+    Error, EventgroupId, MessageId, MessageType, MethodId, ServiceId,
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Services and the data types of their payloads.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    /// Byte order of numbers and of length and type fields
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+    /// Alignment of the members of structs and method parameters in bytes
+    pub alignment: Option<usize>,
+    /// Named data types
+    #[serde(default)]
+    pub types: Vec<TypeDefinition>,
+    /// Services
+    #[serde(default)]
+    pub services: Vec<Service>,
+}
+
+/// Named data type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeDefinition {
+    /// Name referred to by members, parameters and elements
+    pub name: String,
+    /// Kind and encoding of the data type
+    #[serde(flatten)]
+    pub kind: TypeKind,
+}
+
+/// Kind and encoding of a data type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TypeKind {
+    /// Struct
+    Struct {
+        /// Size of the length field in bits
+        #[serde(default)]
+        length_field: u8,
+        /// Alignment of the members in bytes
+        alignment: Option<usize>,
+        /// Members in the order of the encoding
+        members: Vec<Member>,
+    },
+    /// String
+    String {
+        /// Character encoding
+        #[serde(default)]
+        encoding: Encoding,
+        /// Size of the length field in bits
+        length_field: Option<u8>,
+        /// Size of fixed length strings in bytes
+        fixed_size: Option<usize>,
+    },
+    /// Array
+    Array {
+        /// Type of the elements
+        element: String,
+        /// Size of the length field in bits
+        length_field: Option<u8>,
+        /// Number of elements of fixed length arrays
+        length: Option<usize>,
+        /// Smallest number of elements
+        #[serde(default)]
+        min_elements: usize,
+        /// Largest number of elements
+        max_elements: Option<usize>,
+    },
+    /// Union
+    Union {
+        /// Size of the length field in bits
+        length_field: Option<u8>,
+        /// Size of the type field in bits
+        type_field: Option<u8>,
+        /// Alignment of the members in bytes
+        alignment: Option<usize>,
+        /// Members and their type selectors
+        members: Vec<UnionMember>,
+    },
+}
+
+/// Member of a struct or parameter of a method.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Member {
+    /// Name of the member
+    pub name: String,
+    /// Name of the data type
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+/// Member of a union.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnionMember {
+    /// Type selector, 0 is reserved for the empty union
+    pub selector: u32,
+    /// Name of the member
+    pub name: String,
+    /// Name of the data type
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+/// Service interface.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Service {
+    /// Service id
+    pub id: ServiceId,
+    /// Name of the service
+    pub name: String,
+    /// Methods
+    #[serde(default)]
+    pub methods: Vec<Method>,
+    /// Events
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// Fields
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    /// Eventgroups
+    #[serde(default)]
+    pub eventgroups: Vec<Eventgroup>,
+}
+
+/// Method of a service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Method {
+    /// Method id
+    pub id: MethodId,
+    /// Name of the method
+    pub name: String,
+    /// The method is called with `RequestNoReturn` and has no response
+    #[serde(default)]
+    pub fire_and_forget: bool,
+    /// Parameters of the request
+    #[serde(default)]
+    pub request: Vec<Member>,
+    /// Parameters of the response
+    #[serde(default)]
+    pub response: Vec<Member>,
+}
+
+/// Event of a service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    /// Event id, a method id
+    pub id: MethodId,
+    /// Name of the event
+    pub name: String,
+    /// Name of the data type
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+/// Field of a service, accessed by getter, setter and notifier.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Field {
+    /// Name of the field
+    pub name: String,
+    /// Name of the data type
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// Method id of the getter
+    pub getter: Option<MethodId>,
+    /// Method id of the setter
+    pub setter: Option<MethodId>,
+    /// Event id of the notifier
+    pub notifier: Option<MethodId>,
+}
+
+/// Eventgroup of a service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Eventgroup {
+    /// Eventgroup id
+    pub id: EventgroupId,
+    /// Name of the eventgroup
+    pub name: String,
+    /// Ids of the events and field notifiers
+    pub events: Vec<MethodId>,
+}
+
+impl Schema {
+    /// Loads and validates a schema in JSON.
+    pub fn from_json(input: &str) -> Result<Self, Error> {
+        let schema: Schema =
+            serde_json::from_str(input).map_err(|e| Error::InvalidSchema(e.to_string()))?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// Loads and validates a schema in YAML.
+    pub fn from_yaml(input: &str) -> Result<Self, Error> {
+        let schema: Schema =
+            serde_yaml_ng::from_str(input).map_err(|e| Error::InvalidSchema(e.to_string()))?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    /// The service with the given id
+    pub fn service(&self, id: ServiceId) -> Option<&Service> {
+        self.services.iter().find(|service| service.id == id)
+    }
+
+    /// Resolves the data types of the payloads of all methods, events and fields.
+    ///
+    /// Fails with [`Error::InvalidSchema`] on unknown or recursive types,
+    /// duplicate names and ids, conflicting length options, alignments of 0,
+    /// fixed size strings without room for byte order mark and terminator and
+    /// array elements that may be encoded without any bytes.
+    pub fn payload_types(&self) -> Result<PayloadTypes, Error> {
+        let mut payloads = Payloads {
+            resolver: Resolver::new(self)?,
+            types: Some(PayloadTypes::new()),
+        };
+        self.resolve_payloads(&mut payloads)?;
+        Ok(payloads.types.unwrap_or_default())
+    }
+
+    /// Validates the schema like [`Schema::payload_types`] without collecting the payload types.
+    fn validate(&self) -> Result<(), Error> {
+        let mut payloads = Payloads {
+            resolver: Resolver::new(self)?,
+            types: None,
+        };
+        self.resolve_payloads(&mut payloads)
+    }
+
+    fn resolve_payloads(&self, payloads: &mut Payloads) -> Result<(), Error> {
+        for definition in &self.types {
+            payloads
+                .resolver
+                .check(&definition.name, &definition.name)?;
+        }
+
+        for (index, service) in self.services.iter().enumerate() {
+            if self.services[..index]
+                .iter()
+                .any(|other| other.id == service.id)
+            {
+                return Err(invalid(format!("duplicate service id {:#06x}", service.id)));
+            }
+
+            let mut method_ids = Vec::new();
+            let mut unique = |id: MethodId| match method_ids.contains(&id) {
+                true => Err(invalid(format!(
+                    "duplicate method id {id:#06x} in service `{}`",
+                    service.name
+                ))),
+                false => {
+                    method_ids.push(id);
+                    Ok(MessageId::new(service.id, id))
+                }
+            };
+
+            for method in &service.methods {
+                let message_id = unique(method.id)?;
+                if method.fire_and_forget {
+                    if !method.response.is_empty() {
+                        return Err(invalid(format!(
+                            "fire and forget method `{}` has response parameters",
+                            method.name
+                        )));
+                    }
+                    payloads.parameters(
+                        &message_id,
+                        MessageType::RequestNoReturn,
+                        &method.request,
+                        &method.name,
+                    )?;
+                } else {
+                    payloads.parameters(
+                        &message_id,
+                        MessageType::Request,
+                        &method.request,
+                        &method.name,
+                    )?;
+                    payloads.parameters(
+                        &message_id,
+                        MessageType::Response,
+                        &method.response,
+                        &method.name,
+                    )?;
+                }
+            }
+
+            for event in &service.events {
+                let message_id = unique(event.id)?;
+                payloads.named(
+                    &message_id,
+                    MessageType::Notification,
+                    &event.data_type,
+                    &event.name,
+                )?;
+            }
+
+            for field in &service.fields {
+                payloads.resolver.check(&field.data_type, &field.name)?;
+                if field.getter.is_none() && field.setter.is_none() && field.notifier.is_none() {
+                    return Err(invalid(format!(
+                        "field `{}` has no getter, setter or notifier",
+                        field.name
+                    )));
+                }
+                let data_type = field.data_type.as_str();
+                if let Some(getter) = field.getter {
+                    let message_id = unique(getter)?;
+                    payloads.parameters(&message_id, MessageType::Request, &[], &field.name)?;
+                    payloads.named(&message_id, MessageType::Response, data_type, &field.name)?;
+                }
+                if let Some(setter) = field.setter {
+                    let message_id = unique(setter)?;
+                    payloads.named(&message_id, MessageType::Request, data_type, &field.name)?;
+                    payloads.named(&message_id, MessageType::Response, data_type, &field.name)?;
+                }
+                if let Some(notifier) = field.notifier {
+                    let message_id = unique(notifier)?;
+                    payloads.named(
+                        &message_id,
+                        MessageType::Notification,
+                        data_type,
+                        &field.name,
+                    )?;
+                }
+            }
+
+            for (index, eventgroup) in service.eventgroups.iter().enumerate() {
+                if service.eventgroups[..index]
+                    .iter()
+                    .any(|other| other.id == eventgroup.id)
+                {
+                    return Err(invalid(format!(
+                        "duplicate eventgroup id {:#06x} in service `{}`",
+                        eventgroup.id, service.name
+                    )));
+                }
+                for id in &eventgroup.events {
+                    if service.event_name(*id).is_none() {
+                        return Err(invalid(format!(
+                            "unknown event {id:#06x} in eventgroup `{}`",
+                            eventgroup.name
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Service {
+    /// The method with the given id
+    pub fn method(&self, id: MethodId) -> Option<&Method> {
+        self.methods.iter().find(|method| method.id == id)
+    }
+
+    /// The event with the given id
+    pub fn event(&self, id: MethodId) -> Option<&Event> {
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    /// The field with a getter, setter or notifier of the given id
+    pub fn field(&self, id: MethodId) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|field| [field.getter, field.setter, field.notifier].contains(&Some(id)))
+    }
+
+    /// The eventgroup with the given id
+    pub fn eventgroup(&self, id: EventgroupId) -> Option<&Eventgroup> {
+        self.eventgroups
+            .iter()
+            .find(|eventgroup| eventgroup.id == id)
+    }
+
+    /// Name of the event or of the field notifying with the given id
+    fn event_name(&self, id: MethodId) -> Option<&str> {
+        match self.event(id) {
+            Some(event) => Some(&event.name),
+            None => self
+                .fields
+                .iter()
+                .find(|field| field.notifier == Some(id))
+                .map(|field| field.name.as_str()),
+        }
+    }
+}
+
+/// Collects the payload data types, only validates them without `types`.
+struct Payloads<'a> {
+    resolver: Resolver<'a>,
+    types: Option<PayloadTypes>,
+}
+
+impl Payloads<'_> {
+    /// Adds the payload of method parameters.
+    fn parameters(
+        &mut self,
+        message_id: &MessageId,
+        message_type: MessageType,
+        parameters: &[Member],
+        owner: &str,
+    ) -> Result<(), Error> {
+        match &mut self.types {
+            Some(types) => {
+                let data_type = self.resolver.parameters(parameters, owner)?;
+                types.insert(message_id.clone(), message_type, data_type);
+            }
+            None => self.resolver.check_members(parameters, owner)?,
+        }
+        Ok(())
+    }
+
+    /// Adds the payload of the type `name`.
+    fn named(
+        &mut self,
+        message_id: &MessageId,
+        message_type: MessageType,
+        name: &str,
+        owner: &str,
+    ) -> Result<(), Error> {
+        match &mut self.types {
+            Some(types) => {
+                let data_type = self.resolver.resolve(name, owner)?;
+                types.insert(message_id.clone(), message_type, data_type);
+            }
+            None => self.resolver.check(name, owner)?,
+        }
+        Ok(())
+    }
+}
+
+/// Resolves type names into data types.
+struct Resolver<'a> {
+    byte_order: ByteOrder,
+    /// Alignment of struct members without their own alignment
+    alignment: usize,
+    definitions: BTreeMap<&'a str, &'a TypeKind>,
+    /// Resolved definitions, each type is resolved once
+    resolved: RefCell<BTreeMap<&'a str, DataType>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(schema: &'a Schema) -> Result<Self, Error> {
+        if schema.alignment == Some(0) {
+            return Err(invalid("the alignment of the schema is 0".into()));
+        }
+        let mut resolver = Resolver {
+            byte_order: schema.byte_order,
+            alignment: schema.alignment.unwrap_or(1),
+            definitions: BTreeMap::new(),
+            resolved: RefCell::new(BTreeMap::new()),
+        };
+        for definition in &schema.types {
+            let name = definition.name.as_str();
+            if resolver.builtin(name).is_some()
+                || resolver
+                    .definitions
+                    .insert(name, &definition.kind)
+                    .is_some()
+            {
+                return Err(invalid(format!("duplicate type `{name}`")));
+            }
+        }
+        Ok(resolver)
+    }
+
+    /// Data type of the type `name` referred to by `owner`
+    fn resolve(&self, name: &str, owner: &str) -> Result<DataType, Error> {
+        self.resolve_nested(name, owner, &mut Vec::new())
+    }
+
+    /// Checks that the type `name` referred to by `owner` resolves.
+    fn check(&self, name: &str, owner: &str) -> Result<(), Error> {
+        self.with_resolved(name, owner, &mut Vec::new(), |_| ())
+    }
+
+    /// Data type of method parameters, a struct without length field
+    fn parameters(&self, parameters: &[Member], owner: &str) -> Result<DataType, Error> {
+        self.struct_type(parameters, LengthField::None, None, owner, &mut Vec::new())
+    }
+
+    /// Checks the names and types of struct members or method parameters.
+    fn check_members(&self, members: &[Member], owner: &str) -> Result<(), Error> {
+        for (index, member) in members.iter().enumerate() {
+            if members[..index]
+                .iter()
+                .any(|other| other.name == member.name)
+            {
+                return Err(invalid(format!(
+                    "duplicate member `{}` in `{owner}`",
+                    member.name
+                )));
+            }
+            self.check(&member.data_type, owner)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves a type within the definitions of `stack`, detecting recursion.
+    fn resolve_nested(
+        &self,
+        name: &str,
+        owner: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<DataType, Error> {
+        self.with_resolved(name, owner, stack, DataType::clone)
+    }
+
+    /// Calls `f` with the resolved type `name`, resolving it on first use.
+    fn with_resolved<T>(
+        &self,
+        name: &str,
+        owner: &str,
+        stack: &mut Vec<String>,
+        f: impl FnOnce(&DataType) -> T,
+    ) -> Result<T, Error> {
+        if let Some(data_type) = self.builtin(name) {
+            return Ok(f(&data_type));
+        }
+        let Some((&name, kind)) = self.definitions.get_key_value(name) else {
+            return Err(invalid(format!("unknown type `{name}` in `{owner}`")));
+        };
+        if let Some(data_type) = self.resolved.borrow().get(name) {
+            return Ok(f(data_type));
+        }
+        if stack.iter().any(|other| other == name) {
+            return Err(invalid(format!("recursive type `{name}`")));
+        }
+
+        stack.push(name.into());
+        let data_type = self.kind(kind, name, stack);
+        stack.pop();
+        let data_type = data_type?;
+        let result = f(&data_type);
+        self.resolved.borrow_mut().insert(name, data_type);
+        Ok(result)
+    }
+
+    fn kind(
+        &self,
+        kind: &TypeKind,
+        name: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<DataType, Error> {
+        let byte_order = self.byte_order;
+        match kind {
+            TypeKind::Struct {
+                length_field,
+                alignment,
+                members,
+            } => {
+                let length_field = length_field_of(Some(*length_field), name)?;
+                if *alignment == Some(0) {
+                    return Err(invalid(format!("the alignment of `{name}` is 0")));
+                }
+                self.struct_type(members, length_field, *alignment, name, stack)
+            }
+            TypeKind::String {
+                encoding,
+                length_field,
+                fixed_size,
+            } => match (length_field, fixed_size) {
+                (Some(_), Some(_)) => Err(invalid(format!(
+                    "`length_field` and `fixed_size` of `{name}` conflict"
+                ))),
+                (None, Some(size)) => {
+                    let min = encoding.bom().len() + encoding.terminator_len();
+                    if *size < min {
+                        return Err(invalid(format!(
+                            "the fixed size of `{name}` is less than the {min} bytes \
+                             of byte order mark and terminator"
+                        )));
+                    }
+                    if size % encoding.terminator_len() != 0 {
+                        return Err(invalid(format!(
+                            "the fixed size of `{name}` is odd for UTF-16"
+                        )));
+                    }
+                    Ok(DataType::String(StringCodec {
+                        byte_order,
+                        ..StringCodec::fixed(*encoding, *size)
+                    }))
+                }
+                (length_field, None) => Ok(DataType::String(StringCodec {
+                    byte_order,
+                    ..StringCodec::dynamic(*encoding, length_field_of(*length_field, name)?)
+                })),
+            },
+            TypeKind::Array {
+                element,
+                length_field,
+                length,
+                min_elements,
+                max_elements,
+            } => {
+                let codec = match length {
+                    Some(_)
+                        if length_field.is_some()
+                            || *min_elements != 0
+                            || max_elements.is_some() =>
+                    {
+                        return Err(invalid(format!(
+                            "`length` and the dynamic length options of `{name}` conflict"
+                        )))
+                    }
+                    Some(len) => ArrayCodec::fixed(*len),
+                    None => ArrayCodec {
+                        min_elements: *min_elements,
+                        max_elements: *max_elements,
+                        ..ArrayCodec::dynamic(length_field_of(*length_field, name)?)
+                    },
+                };
+                if matches!(codec.max_elements, Some(max) if max < codec.min_elements) {
+                    return Err(invalid(format!(
+                        "`min_elements` of `{name}` exceeds `max_elements`"
+                    )));
+                }
+                let element = self.resolve_nested(element, name, stack)?;
+                // the number of elements without bytes is unknown
+                if codec.fixed_len().is_none() && can_be_empty(&element) {
+                    return Err(invalid(format!(
+                        "the elements of `{name}` may be encoded without any bytes"
+                    )));
+                }
+                Ok(DataType::Array(
+                    ArrayCodec {
+                        byte_order,
+                        ..codec
+                    },
+                    Box::new(element),
+                ))
+            }
+            TypeKind::Union {
+                length_field,
+                type_field,
+                alignment,
+                members,
+            } => {
+                let codec = UnionCodec {
+                    length_field: length_field_of(*length_field, name)?,
+                    type_field: length_field_of(*type_field, name)?,
+                    alignment: alignment.unwrap_or(1),
+                    byte_order,
+                };
+                if codec.type_field == LengthField::None {
+                    return Err(invalid(format!(
+                        "the type field of `{name}` requires 8, 16 or 32 bits"
+                    )));
+                }
+                if codec.alignment == 0 {
+                    return Err(invalid(format!("the alignment of `{name}` is 0")));
+                }
+
+                let mut variants: Vec<UnionVariant> = Vec::new();
+                for member in members {
+                    if member.selector == 0 {
+                        return Err(invalid(format!(
+                            "member `{}` of `{name}` has the reserved type selector 0",
+                            member.name
+                        )));
+                    }
+                    if variants
+                        .iter()
+                        .any(|variant| variant.selector == member.selector)
+                    {
+                        return Err(invalid(format!(
+                            "duplicate type selector {} in `{name}`",
+                            member.selector
+                        )));
+                    }
+                    variants.push(UnionVariant {
+                        selector: member.selector,
+                        name: member.name.clone(),
+                        data_type: self.resolve_nested(&member.data_type, name, stack)?,
+                    });
+                }
+                Ok(DataType::Union(codec, variants))
+            }
+        }
+    }
+
+    fn struct_type(
+        &self,
+        members: &[Member],
+        length_field: LengthField,
+        alignment: Option<usize>,
+        name: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<DataType, Error> {
+        let mut resolved: Vec<payload::Member> = Vec::new();
+        for member in members {
+            if resolved.iter().any(|other| other.name == member.name) {
+                return Err(invalid(format!(
+                    "duplicate member `{}` in `{name}`",
+                    member.name
+                )));
+            }
+            resolved.push(payload::Member {
+                name: member.name.clone(),
+                data_type: self.resolve_nested(&member.data_type, name, stack)?,
+            });
+        }
+        Ok(DataType::Struct(StructType {
+            length_field,
+            byte_order: self.byte_order,
+            alignment: Some(alignment.unwrap_or(self.alignment)),
+            members: resolved,
+        }))
+    }
+
+    /// The predefined type `name`
+    fn builtin(&self, name: &str) -> Option<DataType> {
+        let number = |number_type| Some(DataType::Number(number_type, self.byte_order));
+        match name {
+            "bool" => Some(DataType::Bool),
+            "uint8" => number(NumberType::U8),
+            "uint16" => number(NumberType::U16),
+            "uint32" => number(NumberType::U32),
+            "uint64" => number(NumberType::U64),
+            "int8" => number(NumberType::I8),
+            "int16" => number(NumberType::I16),
+            "int32" => number(NumberType::I32),
+            "int64" => number(NumberType::I64),
+            "float32" => number(NumberType::F32),
+            "float64" => number(NumberType::F64),
+            "string" => Some(DataType::String(StringCodec {
+                byte_order: self.byte_order,
+                ..StringCodec::dynamic(Encoding::Utf8, LengthField::U32)
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Whether values of the data type may be encoded without any bytes
+fn can_be_empty(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Bool | DataType::Number(..) | DataType::String(_) | DataType::Union(..) => false,
+        DataType::Array(codec, element) => {
+            codec.length_field == LengthField::None
+                && (codec.min_elements == 0 || can_be_empty(element))
+        }
+        DataType::Struct(struct_type) => {
+            struct_type.length_field == LengthField::None
+                && struct_type
+                    .members
+                    .iter()
+                    .all(|member| can_be_empty(&member.data_type))
+        }
+    }
+}
+
+/// The length field of `bits` bits of type `name`, 32 bits by default.
+fn length_field_of(bits: Option<u8>, name: &str) -> Result<LengthField, Error> {
+    match bits.unwrap_or(32) {
+        0 => Ok(LengthField::None),
+        8 => Ok(LengthField::U8),
+        16 => Ok(LengthField::U16),
+        32 => Ok(LengthField::U32),
+        bits => Err(invalid(format!(
+            "length field of {bits} bits in `{name}`, expected 0, 8, 16 or 32 bits"
+        ))),
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidSchema(message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        payload::{Number, Value},
+        Message, RequestId,
+    };
+
+    const YAML: &str = r#"
+byte_order: little_endian
+types:
+  - name: Name
+    kind: string
+    encoding: utf16le
+    length_field: 8
+  - name: Matrix
+    kind: array
+    element: Row
+    length_field: 8
+  - name: Row
+    kind: array
+    element: uint8
+    length: 2
+  - name: Shape
+    kind: union
+    length_field: 8
+    type_field: 8
+    members:
+      - { selector: 1, name: circle, type: uint8 }
+      - { selector: 2, name: name, type: Name }
+services:
+  - id: 0x1234
+    name: Drawing
+    methods:
+      - id: 1
+        name: draw
+        request:
+          - { name: shape, type: Shape }
+          - { name: matrix, type: Matrix }
+        response:
+          - { name: ok, type: bool }
+    fields:
+      - { name: count, type: uint16, getter: 2, setter: 3, notifier: 0x8001 }
+    eventgroups:
+      - { id: 1, name: all, events: [0x8001] }
+"#;
+
+    const JSON: &str = r#"{
+        "byte_order": "little_endian",
+        "types": [
+            { "name": "Name", "kind": "string", "encoding": "utf16le", "length_field": 8 },
+            { "name": "Matrix", "kind": "array", "element": "Row", "length_field": 8 },
+            { "name": "Row", "kind": "array", "element": "uint8", "length": 2 },
+            {
+                "name": "Shape", "kind": "union", "length_field": 8, "type_field": 8,
+                "members": [
+                    { "selector": 1, "name": "circle", "type": "uint8" },
+                    { "selector": 2, "name": "name", "type": "Name" }
+                ]
+            }
+        ],
+        "services": [{
+            "id": 4660,
+            "name": "Drawing",
+            "methods": [{
+                "id": 1,
+                "name": "draw",
+                "request": [
+                    { "name": "shape", "type": "Shape" },
+                    { "name": "matrix", "type": "Matrix" }
+                ],
+                "response": [{ "name": "ok", "type": "bool" }]
+            }],
+            "fields": [
+                { "name": "count", "type": "uint16", "getter": 2, "setter": 3, "notifier": 32769 }
+            ],
+            "eventgroups": [{ "id": 1, "name": "all", "events": [32769] }]
+        }]
+    }"#;
+
+    #[test]
+    fn load_json_and_yaml() {
+        let schema = Schema::from_yaml(YAML).unwrap();
+        assert_eq!(Ok(&schema), Schema::from_json(JSON).as_ref());
+
+        let service = schema.service(0x1234).unwrap();
+        assert_eq!("draw", service.method(1).unwrap().name);
+        assert_eq!("count", service.field(0x8001).unwrap().name);
+        assert_eq!(vec![0x8001], service.eventgroup(1).unwrap().events);
+        assert!(schema.service(0x1235).is_none());
+    }
+
+    #[test]
+    fn decode_with_schema() {
+        let types = Schema::from_yaml(YAML).unwrap().payload_types().unwrap();
+        let payload: &[u8] = &[
+            0x01, 0x01, 0x07, // length(u8), type(u8), circle(u8)
+            0x04, 0x01, 0x02, 0x03, 0x04, // length(u8), matrix
+        ];
+        let message = Message::request(
+            MessageId::new(0x1234, 0x0001),
+            RequestId::new(0x0001, 0x0001),
+            0x01,
+            payload,
+        );
+        let node = types.decode(&message).unwrap();
+        let circle = node.value.field("shape").unwrap().value.field("circle");
+        assert_eq!(
+            Some(&Value::Number(Number::U8(7))),
+            circle.map(|node| &node.value)
+        );
+        assert_eq!(3, node.value.field("matrix").unwrap().offset);
+
+        // fields in little endian byte order
+        let count = MessageId::new(0x1234, 0x8001);
+        let value = Value::Number(Number::U16(0x0102));
+        assert_eq!(
+            Ok(vec![0x02, 0x01]),
+            types.encode(&count, MessageType::Notification, &value)
+        );
+        let getter = MessageId::new(0x1234, 0x0002);
+        assert_eq!(
+            Ok(vec![]),
+            types.encode(&getter, MessageType::Request, &Value::Struct(vec![]))
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let types = Schema::from_yaml(
+            r#"
+alignment: 4
+types:
+  - { name: Text, kind: string, length_field: 8 }
+  - name: Label
+    kind: struct
+    length_field: 8
+    alignment: 1
+    members: [{ name: text, type: Text }, { name: id, type: uint8 }]
+services:
+  - id: 1
+    name: A
+    methods:
+      - id: 1
+        name: set
+        request: [{ name: text, type: Text }, { name: id, type: uint8 }]
+    events:
+      - { id: 0x8001, name: label, type: Label }
+"#,
+        )
+        .unwrap()
+        .payload_types()
+        .unwrap();
+
+        let request: &[u8] = &[
+            0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), text
+            0x00, 0x00, // padding
+            0x07, // id(u8)
+        ];
+        let set = MessageId::new(0x0001, 0x0001);
+        let message = Message::request(set.clone(), RequestId::new(0x0001, 0x0001), 0x01, request);
+        let node = types.decode(&message).unwrap();
+        assert_eq!(8, node.value.field("id").unwrap().offset);
+        assert_eq!(
+            Ok(request.to_vec()),
+            types.encode(&set, MessageType::Request, &node.value)
+        );
+
+        let event: &[u8] = &[
+            0x07, // length(u8)
+            0x05, 0xEF, 0xBB, 0xBF, 0x61, 0x00, // length(u8), text
+            0x07, // id(u8)
+        ];
+        let label = MessageId::new(0x0001, 0x8001);
+        let message = Message::notification(label, RequestId::new(0x0001, 0x0001), 0x01, event);
+        let node = types.decode(&message).unwrap();
+        assert_eq!(7, node.value.field("id").unwrap().offset);
+    }
+
+    fn error(yaml: &str) -> String {
+        match Schema::from_yaml(yaml) {
+            Err(Error::InvalidSchema(message)) => message,
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        assert_eq!(
+            "unknown type `Point` in `Status`",
+            error("types: [{ name: Status, kind: struct, members: [{ name: p, type: Point }] }]")
+        );
+        assert_eq!(
+            "recursive type `List`",
+            error("types: [{ name: List, kind: array, element: List }]")
+        );
+        assert_eq!(
+            "duplicate type `uint8`",
+            error("types: [{ name: uint8, kind: struct, members: [] }]")
+        );
+        assert_eq!(
+            "`length_field` and `fixed_size` of `Name` conflict",
+            error("types: [{ name: Name, kind: string, length_field: 8, fixed_size: 8 }]")
+        );
+        assert_eq!(
+            "`length` and the dynamic length options of `Row` conflict",
+            error(
+                "types: [{ name: Row, kind: array, element: uint8, length: 2, length_field: 8 }]"
+            )
+        );
+        assert_eq!(
+            "length field of 24 bits in `Name`, expected 0, 8, 16 or 32 bits",
+            error("types: [{ name: Name, kind: string, length_field: 24 }]")
+        );
+        assert_eq!(
+            "duplicate method id 0x0001 in service `A`",
+            error(
+                "services: [{ id: 1, name: A, methods: [{ id: 1, name: a }], \
+                 events: [{ id: 1, name: b, type: bool }] }]"
+            )
+        );
+        assert_eq!(
+            "duplicate service id 0x0001",
+            error("services: [{ id: 1, name: A }, { id: 1, name: B }]")
+        );
+        assert_eq!(
+            "unknown event 0x8001 in eventgroup `all`",
+            error("services: [{ id: 1, name: A, eventgroups: [{ id: 1, name: all, events: [0x8001] }] }]")
+        );
+        assert!(error("services: [{ id: 1, name: A, version: 2 }]").contains("unknown field"));
+        assert_eq!(
+            "the elements of `List` may be encoded without any bytes",
+            error(
+                "types: [{ name: Empty, kind: struct, members: [] }, \
+                 { name: List, kind: array, element: Empty }]"
+            )
+        );
+        assert_eq!(
+            "the elements of `Rows` may be encoded without any bytes",
+            error(
+                "types: [{ name: Row, kind: array, element: uint8, length_field: 0 }, \
+                 { name: Rows, kind: array, element: Row }]"
+            )
+        );
+        assert_eq!(
+            "the fixed size of `Name` is less than the 4 bytes of byte order mark and terminator",
+            error("types: [{ name: Name, kind: string, fixed_size: 0 }]")
+        );
+        assert_eq!(
+            "the fixed size of `Name` is less than the 4 bytes of byte order mark and terminator",
+            error("types: [{ name: Name, kind: string, encoding: utf16be, fixed_size: 3 }]")
+        );
+        assert_eq!(
+            "the fixed size of `Name` is odd for UTF-16",
+            error("types: [{ name: Name, kind: string, encoding: utf16le, fixed_size: 9 }]")
+        );
+        assert_eq!(
+            "the alignment of `Point` is 0",
+            error("types: [{ name: Point, kind: struct, alignment: 0, members: [] }]")
+        );
+        assert_eq!("the alignment of the schema is 0", error("alignment: 0"));
+        // fixed length arrays of empty elements have a known size
+        assert!(Schema::from_yaml(
+            "types: [{ name: Empty, kind: struct, members: [] }, \
+             { name: Pair, kind: array, element: Empty, length: 2 }]"
+        )
+        .is_ok());
+    }
+}
@@ -1,220 +1,386 @@
 use crate::{types::*, Error};
-use byteorder::{BigEndian, WriteBytesExt};
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::net::IpAddr;
+#[cfg(feature = "std")]
 use std::io::Write;
-use std::net::IpAddr;
 
+#[cfg(feature = "alloc")]
 impl<'a> Message<'a> {
-    /// Serializes the message into a byte array.
-    pub fn to_vec(&self) -> Vec<u8> {
-        let len = match self {
+    /// Length of the serialized message in bytes
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
             Message::Rpc(_, payload) => Header::LENGTH + payload.len(),
             Message::Sd(_, payload) => Header::LENGTH + payload.len(),
             Message::CookieClient | Message::CookieServer => Header::LENGTH,
-        };
+        }
+    }
 
-        let mut buffer = Vec::with_capacity(len);
-        self.to_writer(&mut buffer).unwrap(); // Safe because it is a Vec
-        buffer
+    /// Checks that the length field of the header matches the payload.
+    ///
+    /// All serialization functions fail with this error instead of
+    /// writing an inconsistent message.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Message::Rpc(header, payload) => header.validate_payload_len(payload.len()),
+            Message::Sd(header, payload) => header.validate_payload_len(payload.len()),
+            Message::CookieClient | Message::CookieServer => Ok(()),
+        }
+    }
+
+    /// Serializes the message into a byte array.
+    pub fn to_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut buffer = vec![0u8; self.len()];
+        self.to_slice(&mut buffer)?;
+        Ok(buffer)
     }
 
     /// Serialize the message into a writer.
+    ///
+    /// The header and a borrowed payload are written with vectored writes,
+    /// an SD payload is streamed into the writer without buffering it.
+    #[cfg(feature = "std")]
     pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
         match self {
-            Message::Rpc(header, payload) => {
-                header.to_writer(&mut writer)?;
-                writer.write_all(payload)?;
-                Ok(Header::LENGTH + payload.len())
-            }
             Message::Sd(header, payload) => {
+                self.validate()?;
                 header.to_writer(&mut writer)?;
                 payload.to_writer(&mut writer)?;
-                Ok(Header::LENGTH + payload.len())
+                Ok(self.len())
             }
-            Message::CookieClient => {
-                const MAGIC_COOKIE_CLIENT: Header = Header {
-                    message_id: MessageId {
-                        service_id: 0xFFFF,
-                        method_id: 0x0000,
-                    },
-                    length: 8,
-                    request_id: RequestId {
-                        client_id: 0xDEAD,
-                        session_id: 0xBEEF,
-                    },
-                    protocol_version: 0x01,
-                    interface_version: 0x01,
-                    message_type: MessageType::RequestNoReturn,
-                    return_code: ReturnCode::Ok,
-                };
-                MAGIC_COOKIE_CLIENT
-                    .to_writer(writer)
-                    .map(|_| Header::LENGTH)
+            _ => self.to_vectored()?.write_to(writer),
+        }
+    }
+
+    /// Serialize the message into a byte slice.
+    ///
+    /// Returns the number of bytes written or [`Error::BufferTooSmall`]
+    /// if the slice cannot hold the message.
+    pub fn to_slice(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        self.validate()?;
+        let len = self.len();
+        let mut writer = SliceWriter::new(buffer, len)?;
+
+        match self {
+            Message::Rpc(header, payload) => {
+                header.encode(&mut writer);
+                writer.put(payload);
             }
-            Message::CookieServer => {
-                const MAGIC_COOKIE: Header = Header {
-                    message_id: MessageId {
-                        service_id: 0xFFFF,
-                        method_id: 0x8000,
-                    },
-                    length: 8,
-                    request_id: RequestId {
-                        client_id: 0xDEAD,
-                        session_id: 0xBEEF,
-                    },
-                    protocol_version: 0x01,
-                    interface_version: 0x01,
-                    message_type: MessageType::Notification,
-                    return_code: ReturnCode::Ok,
-                };
-                MAGIC_COOKIE.to_writer(writer).map(|_| Header::LENGTH)
+            Message::Sd(header, payload) => {
+                header.encode(&mut writer);
+                payload.encode(&mut writer);
             }
+            Message::CookieClient => MAGIC_COOKIE_CLIENT.encode(&mut writer),
+            Message::CookieServer => MAGIC_COOKIE_SERVER.encode(&mut writer),
         }
+
+        Ok(len)
     }
 }
 
+#[cfg(feature = "alloc")]
+pub(crate) const MAGIC_COOKIE_CLIENT: Header = Header {
+    message_id: MessageId {
+        service_id: 0xFFFF,
+        method_id: 0x0000,
+    },
+    length: 8,
+    request_id: RequestId {
+        client_id: 0xDEAD,
+        session_id: 0xBEEF,
+    },
+    protocol_version: 0x01,
+    interface_version: 0x01,
+    message_type: MessageType::RequestNoReturn,
+    return_code: ReturnCode::Ok,
+};
+
+#[cfg(feature = "alloc")]
+pub(crate) const MAGIC_COOKIE_SERVER: Header = Header {
+    message_id: MessageId {
+        service_id: 0xFFFF,
+        method_id: 0x8000,
+    },
+    length: 8,
+    request_id: RequestId {
+        client_id: 0xDEAD,
+        session_id: 0xBEEF,
+    },
+    protocol_version: 0x01,
+    interface_version: 0x01,
+    message_type: MessageType::Notification,
+    return_code: ReturnCode::Ok,
+};
+
 impl Header {
     /// Serializes the header into a byte array.
+    #[cfg(feature = "alloc")]
     pub fn to_vec(&self) -> Vec<u8> {
-        let mut buffer = Vec::with_capacity(Header::LENGTH);
-        self.to_writer(&mut buffer).unwrap(); // Safe because it is a Vec
+        let mut buffer = vec![0u8; Header::LENGTH];
+        self.to_slice(&mut buffer).unwrap(); // Safe because the length is fixed
+        buffer
+    }
+
+    /// Checks that the length field matches a payload of the given length.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn validate_payload_len(&self, payload_len: usize) -> Result<(), Error> {
+        if self.length < 8 {
+            return Err(Error::InvalidLengthField(self.length));
+        }
+        if self.payload_len() != payload_len {
+            return Err(Error::PayloadLengthMismatch {
+                header: self.payload_len(),
+                payload: payload_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Serializes the header into its fixed size byte representation.
+    pub fn to_bytes(&self) -> [u8; Header::LENGTH] {
+        let mut buffer = [0u8; Header::LENGTH];
+        self.to_slice(&mut buffer).unwrap(); // Safe because the length is fixed
         buffer
     }
 
     /// Serialize the header into a writer.
+    #[cfg(feature = "std")]
     pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
-        writer.write_u16::<BigEndian>(self.message_id.service_id)?;
-        writer.write_u16::<BigEndian>(self.message_id.method_id)?;
-        writer.write_u32::<BigEndian>(self.length)?;
-        writer.write_u16::<BigEndian>(self.request_id.client_id)?;
-        writer.write_u16::<BigEndian>(self.request_id.session_id)?;
-        writer.write_u8(self.protocol_version)?;
-        writer.write_u8(self.interface_version)?;
-        writer.write_u8(self.message_type.into())?;
-        writer.write_u8(self.return_code.into())?;
+        writer.write_all(&self.to_bytes())?;
+
+        Ok(Header::LENGTH)
+    }
+
+    /// Serialize the header into a byte slice.
+    ///
+    /// Returns the number of bytes written or [`Error::BufferTooSmall`]
+    /// if the slice cannot hold the header.
+    pub fn to_slice(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = SliceWriter::new(buffer, Header::LENGTH)?;
+        self.encode(&mut writer);
 
         Ok(Header::LENGTH)
     }
+
+    pub(crate) fn encode(&self, writer: &mut impl Sink) {
+        writer.put_u16(self.message_id.service_id);
+        writer.put_u16(self.message_id.method_id);
+        writer.put_u32(self.length);
+        writer.put_u16(self.request_id.client_id);
+        writer.put_u16(self.request_id.session_id);
+        writer.put_u8(self.protocol_version);
+        writer.put_u8(self.interface_version);
+        writer.put_u8(self.message_type.into());
+        writer.put_u8(self.return_code.into());
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl SdPayload {
     /// Serializes the payload into a writer.
-    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
-        writer.write_u8(self.flags)?;
-        writer.write_u24::<BigEndian>(0x000000)?; // reserved
+    #[cfg(feature = "std")]
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<usize, Error> {
+        let mut writer = IoWriter::new(writer);
+        self.encode(&mut writer);
+        writer.finish()?;
 
-        writer.write_u32::<BigEndian>(self.entries_len() as u32)?;
+        Ok(self.len())
+    }
+
+    /// Serialize the payload into a byte slice.
+    ///
+    /// Returns the number of bytes written or [`Error::BufferTooSmall`]
+    /// if the slice cannot hold the payload.
+    pub fn to_slice(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let len = self.len();
+        let mut writer = SliceWriter::new(buffer, len)?;
+        self.encode(&mut writer);
+
+        Ok(len)
+    }
+
+    pub(crate) fn encode(&self, writer: &mut impl Sink) {
+        writer.put_u8(self.flags);
+        writer.put_u24(0x000000); // reserved
+
+        writer.put_u32(self.entries_len() as u32);
         for entry in &self.entries {
-            entry.to_writer(&mut writer)?;
+            entry.encode(writer);
         }
 
-        writer.write_u32::<BigEndian>(self.options_len() as u32)?;
+        writer.put_u32(self.options_len() as u32);
         for option in &self.options {
-            option.to_writer(&mut writer)?;
+            option.encode(writer);
         }
-
-        Ok(self.len())
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdEntry {
-    fn to_writer<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
+    fn encode(&self, writer: &mut impl Sink) {
         let entry_type: u8 = self.into();
 
         match self {
-            SdEntry::FindService(item) => item.to_writer(entry_type, &mut writer),
-            SdEntry::OfferService(item) => item.to_writer(entry_type, &mut writer),
-            SdEntry::SubscribeEventgroup(item) => item.to_writer(entry_type, &mut writer),
-            SdEntry::SubscribeEventgroupAck(item) => item.to_writer(entry_type, &mut writer),
+            SdEntry::FindService(item) => item.encode(entry_type, writer),
+            SdEntry::OfferService(item) => item.encode(entry_type, writer),
+            SdEntry::SubscribeEventgroup(item) => item.encode(entry_type, writer),
+            SdEntry::SubscribeEventgroupAck(item) => item.encode(entry_type, writer),
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdServiceEntry {
-    fn to_writer<W: Write>(&self, entry_type: u8, writer: &mut W) -> Result<usize, Error> {
-        writer.write_u8(entry_type)?;
-        self.options.to_writer(writer)?;
-
-        writer.write_u16::<BigEndian>(self.service_id)?;
-        writer.write_u16::<BigEndian>(self.instance_id)?;
-        writer.write_u8(self.major_version)?;
-        writer.write_u24::<BigEndian>(self.ttl)?;
-        writer.write_u32::<BigEndian>(self.minor_version)?;
-
-        Ok(SdEntry::LENGTH)
+    fn encode(&self, entry_type: u8, writer: &mut impl Sink) {
+        writer.put_u8(entry_type);
+        self.options.encode(writer);
+
+        writer.put_u16(self.service_id);
+        writer.put_u16(self.instance_id);
+        writer.put_u8(self.major_version);
+        writer.put_u24(self.ttl);
+        writer.put_u32(self.minor_version);
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdEventgroupEntry {
-    fn to_writer<W: Write>(&self, entry_type: u8, writer: &mut W) -> Result<usize, Error> {
-        writer.write_u8(entry_type)?;
-        self.options.to_writer(writer)?;
-        writer.write_u16::<BigEndian>(self.service_id)?;
-        writer.write_u16::<BigEndian>(self.instance_id)?;
-        writer.write_u8(self.major_version)?;
-        writer.write_u24::<BigEndian>(self.ttl)?;
-        writer.write_u16::<BigEndian>(0x0000)?; // reserved
-        writer.write_u16::<BigEndian>(self.eventgroup_id)?;
-
-        Ok(SdEntry::LENGTH)
+    fn encode(&self, entry_type: u8, writer: &mut impl Sink) {
+        writer.put_u8(entry_type);
+        self.options.encode(writer);
+        writer.put_u16(self.service_id);
+        writer.put_u16(self.instance_id);
+        writer.put_u8(self.major_version);
+        writer.put_u24(self.ttl);
+        writer.put_u16(0x0000); // reserved
+        writer.put_u16(self.eventgroup_id);
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdOptionRef {
-    fn to_writer<W: Write>(&self, writer: &mut W) -> Result<usize, Error> {
-        writer.write_u8(self.index1)?;
-        writer.write_u8(self.index2)?;
-        let mut num: u8 = 0x00;
-        num |= (self.num1 << 4) & 0xF0;
-        num |= self.num2 & 0x0F;
-        writer.write_u8(num)?;
-
-        Ok(3)
+    fn encode(&self, writer: &mut impl Sink) {
+        writer.put_u8(self.index1);
+        writer.put_u8(self.index2);
+        writer.put_u8(((self.num1 << 4) & 0xF0) | (self.num2 & 0x0F));
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdOption {
-    fn to_writer<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
+    fn encode(&self, writer: &mut impl Sink) {
         let option_type: u8 = self.into();
         let option_len = self.len();
 
         match self {
-            SdOption::Ip4Unicast(item) => item.to_writer(option_type, option_len, &mut writer),
-            SdOption::Ip4Multicast(item) => item.to_writer(option_type, option_len, &mut writer),
-            SdOption::Ip6Unicast(item) => item.to_writer(option_type, option_len, &mut writer),
-            SdOption::Ip6Multicast(item) => item.to_writer(option_type, option_len, &mut writer),
+            SdOption::Ip4Unicast(item) => item.encode(option_type, option_len, writer),
+            SdOption::Ip4Multicast(item) => item.encode(option_type, option_len, writer),
+            SdOption::Ip6Unicast(item) => item.encode(option_type, option_len, writer),
+            SdOption::Ip6Multicast(item) => item.encode(option_type, option_len, writer),
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl SdEndpointOption {
-    fn to_writer<W: Write>(
-        &self,
-        option_type: u8,
-        option_len: usize,
-        writer: &mut W,
-    ) -> Result<usize, Error> {
-        writer.write_u16::<BigEndian>(option_len as u16 - 3)?;
-        writer.write_u8(option_type)?;
-        writer.write_u8(0x00)?; // reserved
+    fn encode(&self, option_type: u8, option_len: usize, writer: &mut impl Sink) {
+        writer.put_u16(option_len as u16 - 3);
+        writer.put_u8(option_type);
+        writer.put_u8(0x00); // reserved
 
         match &self.ip {
-            IpAddr::V4(ip4) => {
-                for octet in ip4.octets() {
-                    writer.write_u8(octet)?;
-                }
-            }
-            IpAddr::V6(ip6) => {
-                for segement in ip6.segments() {
-                    writer.write_u16::<BigEndian>(segement)?;
-                }
-            }
+            IpAddr::V4(ip4) => writer.put(&ip4.octets()),
+            IpAddr::V6(ip6) => writer.put(&ip6.octets()),
         }
 
-        writer.write_u8(0x00)?; // reserved
-        writer.write_u8(self.proto.into())?;
-        writer.write_u16::<BigEndian>(self.port)?;
+        writer.put_u8(0x00); // reserved
+        writer.put_u8(self.proto.into());
+        writer.put_u16(self.port);
+    }
+}
+
+/// Destination of big endian values.
+///
+/// The capacity of a sink is checked once before encoding or a sink keeps
+/// its first error, so writing the values themselves cannot fail.
+pub(crate) trait Sink {
+    fn put(&mut self, bytes: &[u8]);
+
+    fn put_u8(&mut self, value: u8) {
+        self.put(&[value]);
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.put(&value.to_be_bytes());
+    }
+
+    #[cfg(feature = "alloc")]
+    fn put_u24(&mut self, value: u32) {
+        self.put(&value.to_be_bytes()[1..]);
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.put(&value.to_be_bytes());
+    }
+}
+
+/// Writes big endian values into a byte slice.
+struct SliceWriter<'b> {
+    buffer: &'b mut [u8],
+    pos: usize,
+}
+
+impl<'b> SliceWriter<'b> {
+    fn new(buffer: &'b mut [u8], required: usize) -> Result<Self, Error> {
+        if buffer.len() < required {
+            return Err(Error::BufferTooSmall {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        Ok(Self { buffer, pos: 0 })
+    }
+}
+
+impl<'b> Sink for SliceWriter<'b> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buffer[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+}
+
+/// Writes big endian values into an `io::Write`, keeping the first error.
+#[cfg(feature = "std")]
+struct IoWriter<W> {
+    writer: W,
+    result: std::io::Result<()>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> IoWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            result: Ok(()),
+        }
+    }
 
-        Ok(option_len)
+    /// The first error of the writer
+    fn finish(self) -> std::io::Result<()> {
+        self.result
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Sink for IoWriter<W> {
+    fn put(&mut self, bytes: &[u8]) {
+        if self.result.is_ok() {
+            self.result = self.writer.write_all(bytes);
+        }
     }
 }
 
@@ -233,7 +399,7 @@ mod tests {
             0x01, 0x01, 0x01, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
         ];
 
-        assert_eq!(header, Message::CookieClient.to_vec());
+        assert_eq!(header, Message::CookieClient.to_vec().unwrap());
     }
 
     #[test]
@@ -245,7 +411,7 @@ mod tests {
             0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
         ];
 
-        assert_eq!(header, Message::CookieServer.to_vec());
+        assert_eq!(header, Message::CookieServer.to_vec().unwrap());
     }
 
     #[test]
@@ -278,6 +444,7 @@ mod tests {
                 &[],
             )
             .to_vec()
+            .unwrap()
         );
     }
 
@@ -313,6 +480,7 @@ mod tests {
                 payload,
             )
             .to_vec()
+            .unwrap()
         );
     }
 
@@ -320,7 +488,7 @@ mod tests {
     fn serialize_empty_sd_message() {
         let header: &[u8] = &[
             0xFF, 0xFF, 0x81, 0x00, // serviceId(u16), methodId(u16)
-            0x00, 0x00, 0x00, 0x0C, // length(u32)
+            0x00, 0x00, 0x00, 0x14, // length(u32)
             0x00, 0x00, 0x00, 0x00, // clientId(u16), sessionId(u16)
             0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
         ];
@@ -339,7 +507,7 @@ mod tests {
                         service_id: 0xFFFF,
                         method_id: 0x8100,
                     },
-                    length: 12,
+                    length: 20,
                     request_id: RequestId {
                         client_id: 0x0000,
                         session_id: 0x0000,
@@ -356,6 +524,27 @@ mod tests {
                 },
             )
             .to_vec()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_sd_payload_into_writer() {
+        let payload = SdPayload {
+            flags: 0xC0,
+            entries: vec![],
+            options: vec![],
+        };
+        let mut buffer = Vec::new();
+        assert_eq!(12, payload.to_writer(&mut buffer).unwrap());
+        let mut expected = [0u8; 12];
+        payload.to_slice(&mut expected).unwrap();
+        assert_eq!(expected.to_vec(), buffer);
+
+        let mut buffer = [0u8; 11];
+        assert_eq!(
+            Err(Error::Io(std::io::ErrorKind::WriteZero)),
+            payload.to_writer(&mut buffer[..])
         );
     }
 
@@ -418,6 +607,7 @@ mod tests {
                 },
             )
             .to_vec()
+            .unwrap()
         );
     }
 
@@ -505,6 +695,7 @@ mod tests {
                 },
             )
             .to_vec()
+            .unwrap()
         );
     }
 
@@ -595,6 +786,99 @@ mod tests {
                 },
             )
             .to_vec()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_message_to_slice() {
+        let message = Message::Sd(
+            Header {
+                message_id: MessageId {
+                    service_id: 0xFFFF,
+                    method_id: 0x8100,
+                },
+                length: 60,
+                request_id: RequestId {
+                    client_id: 0x0000,
+                    session_id: 0x0001,
+                },
+                protocol_version: 0x01,
+                interface_version: 0x01,
+                message_type: MessageType::Notification,
+                return_code: ReturnCode::Ok,
+            },
+            SdPayload {
+                flags: 0xC0,
+                entries: vec![SdEntry::SubscribeEventgroup(SdEventgroupEntry {
+                    service_id: 0x0103,
+                    eventgroup_id: 0x01C8,
+                    instance_id: 0x0001,
+                    major_version: 0x02,
+                    ttl: 0x00000003,
+                    options: SdOptionRef {
+                        index1: 0,
+                        index2: 0,
+                        num1: 1,
+                        num2: 0,
+                    },
+                })],
+                options: vec![SdOption::Ip6Unicast(SdEndpointOption {
+                    ip: IpAddr::V6(Ipv6Addr::from_str("FF0E::FFFF:EFC0:FFFB").unwrap()),
+                    port: 30000,
+                    proto: IpProto::TCP,
+                })],
+            },
+        );
+
+        let mut buffer = [0xAAu8; 72];
+        assert_eq!(68, message.to_slice(&mut buffer).unwrap());
+        assert_eq!(message.to_vec().unwrap(), &buffer[..68]);
+        assert_eq!(&[0xAA; 4], &buffer[68..]);
+
+        if let Message::Sd(header, payload) = &message {
+            assert_eq!(16, header.to_slice(&mut buffer).unwrap());
+            assert_eq!(header.to_vec(), &buffer[..16]);
+            assert_eq!(52, payload.to_slice(&mut buffer).unwrap());
+            assert_eq!(&message.to_vec().unwrap()[16..], &buffer[..52]);
+        }
+    }
+
+    #[test]
+    fn serialize_to_slice_buffer_too_small() {
+        let message = Message::Rpc(
+            Header {
+                message_id: MessageId {
+                    service_id: 0x0103,
+                    method_id: 0x8005,
+                },
+                length: 13,
+                request_id: RequestId {
+                    client_id: 0x0001,
+                    session_id: 0x0002,
+                },
+                protocol_version: 0x01,
+                interface_version: 0x01,
+                message_type: MessageType::Notification,
+                return_code: ReturnCode::Ok,
+            },
+            &[0x01, 0x02, 0x03, 0x04, 0x05],
+        );
+
+        assert_eq!(
+            Err(Error::BufferTooSmall {
+                required: 21,
+                available: 20
+            }),
+            message.to_slice(&mut [0u8; 20])
+        );
+        assert_eq!(
+            Err(Error::BufferTooSmall {
+                required: 16,
+                available: 15
+            }),
+            Message::CookieClient.to_slice(&mut [0u8; 15])
         );
+        assert_eq!(16, Message::CookieServer.to_slice(&mut [0u8; 16]).unwrap());
     }
 }
@@ -1,7 +1,10 @@
 use crate::Error;
 use derive_builder::Builder;
 
-use std::net::IpAddr;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::net::IpAddr;
 
 /// Length trait for items with a fixed length.
 pub trait Length {
@@ -9,7 +12,18 @@ pub trait Length {
     const LENGTH: usize;
 }
 
+/// Defines how values outside the known ranges are handled while parsing.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Unknown message types and return codes are rejected
+    #[default]
+    Strict,
+    /// Unknown message types and return codes are preserved as `Unknown` values
+    Lenient,
+}
+
 /// Different kinds of SOME/IP messages.
+#[cfg(feature = "alloc")]
 #[derive(Debug, Eq, PartialEq)]
 pub enum Message<'a> {
     /// RPC Message
@@ -24,9 +38,97 @@ pub enum Message<'a> {
     CookieServer,
 }
 
+#[cfg(feature = "alloc")]
+impl<'a> Message<'a> {
+    /// Get the header of the message (if not a magic cookie)
+    pub fn header(&self) -> Option<&Header> {
+        match self {
+            Message::Rpc(header, _) | Message::Sd(header, _) => Some(header),
+            Message::CookieClient | Message::CookieServer => None,
+        }
+    }
+}
+
+/// Owned counterpart of [`Message`] that does not borrow its payload.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MessageBuf {
+    /// RPC Message
+    Rpc(Header, Vec<u8>),
+    /// SD Message
+    Sd(Header, SdPayload),
+    /// Magic Cookie Client
+    /// RS_SOMEIP_00010
+    CookieClient,
+    /// Magic Cookie Server
+    /// RS_SOMEIP_00010
+    CookieServer,
+}
+
+#[cfg(feature = "alloc")]
+impl MessageBuf {
+    /// Parse a message from a byte slice and copy its payload.
+    pub fn from_slice(input: &[u8]) -> Result<MessageBuf, Error> {
+        Message::from_slice(input).map(MessageBuf::from)
+    }
+
+    /// Get the header of the message (if not a magic cookie)
+    pub fn header(&self) -> Option<&Header> {
+        match self {
+            MessageBuf::Rpc(header, _) | MessageBuf::Sd(header, _) => Some(header),
+            MessageBuf::CookieClient | MessageBuf::CookieServer => None,
+        }
+    }
+
+    /// Borrow the message as a [`Message`].
+    pub fn as_message(&self) -> Message<'_> {
+        self.into()
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Transforms a Message to a MessageBuf by copying the payload.
+impl<'a> From<Message<'a>> for MessageBuf {
+    fn from(message: Message<'a>) -> Self {
+        match message {
+            Message::Rpc(header, payload) => MessageBuf::Rpc(header, payload.to_vec()),
+            Message::Sd(header, payload) => MessageBuf::Sd(header, payload),
+            Message::CookieClient => MessageBuf::CookieClient,
+            Message::CookieServer => MessageBuf::CookieServer,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Transforms a Message reference to a MessageBuf by copying the payload.
+impl<'a> From<&Message<'a>> for MessageBuf {
+    fn from(message: &Message<'a>) -> Self {
+        match message {
+            Message::Rpc(header, payload) => MessageBuf::Rpc(header.clone(), payload.to_vec()),
+            Message::Sd(header, payload) => MessageBuf::Sd(header.clone(), payload.clone()),
+            Message::CookieClient => MessageBuf::CookieClient,
+            Message::CookieServer => MessageBuf::CookieServer,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Transforms a MessageBuf reference to a Message borrowing the payload.
+impl<'a> From<&'a MessageBuf> for Message<'a> {
+    fn from(message: &'a MessageBuf) -> Self {
+        match message {
+            MessageBuf::Rpc(header, payload) => Message::Rpc(header.clone(), payload),
+            MessageBuf::Sd(header, payload) => Message::Sd(header.clone(), payload.clone()),
+            MessageBuf::CookieClient => Message::CookieClient,
+            MessageBuf::CookieServer => Message::CookieServer,
+        }
+    }
+}
+
 /// Represents the header of a SOME/IP message.
 #[derive(Builder, Clone, Debug, Eq, PartialEq)]
 #[builder(pattern = "mutable")]
+#[cfg_attr(not(feature = "std"), builder(no_std))]
 pub struct Header {
     /// Message id
     pub message_id: MessageId,
@@ -80,8 +182,10 @@ impl Header {
     }
 
     /// The length of the message associated with the header
+    ///
+    /// Saturates at `usize::MAX` on 32-bit targets.
     pub fn message_len(&self) -> usize {
-        self.length as usize + 8
+        (self.length as usize).saturating_add(8)
     }
 
     /// The length of the payload associated with the header
@@ -126,21 +230,11 @@ impl Header {
 
     /// Returns true if the header indicates a SD message
     pub fn is_sd(&self) -> bool {
-        matches!(
-            self,
-            Header {
-                message_id: MessageId {
-                    service_id: 0xFFFF,
-                    method_id: 0x8100,
-                },
-                length: _,
-                request_id: _,
-                protocol_version: 0x01,
-                interface_version: 0x01,
-                message_type: MessageType::Notification,
-                return_code: ReturnCode::Ok,
-            }
-        )
+        self.message_id == MessageId::new(0xFFFF, 0x8100)
+            && self.protocol_version == 0x01
+            && self.interface_version == 0x01
+            && self.message_type == MessageType::Notification
+            && self.return_code == ReturnCode::Ok
     }
 }
 
@@ -161,7 +255,7 @@ pub struct MessageId {
 
 impl MessageId {
     /// Construct a new MessageId
-    pub fn new(service_id: ServiceId, method_id: MethodId) -> Self {
+    pub const fn new(service_id: ServiceId, method_id: MethodId) -> Self {
         Self {
             service_id,
             method_id,
@@ -206,7 +300,7 @@ pub struct RequestId {
 
 impl RequestId {
     /// Construct a new RequestId
-    pub fn new(client_id: ClientId, session_id: SessionId) -> Self {
+    pub const fn new(client_id: ClientId, session_id: SessionId) -> Self {
         Self {
             client_id,
             session_id,
@@ -257,7 +351,11 @@ pub type ProtocolVersion = u8;
 pub type InterfaceVersion = u8;
 
 /// Different kinds of MessageType accepted in a header.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+///
+/// Values are compared by the byte they encode to, so `Unknown(0x00)`
+/// equals `Request`. Match on [`MessageType::normalize`] to treat them alike.
+#[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub enum MessageType {
     /// Request
     Request,
@@ -279,6 +377,64 @@ pub enum MessageType {
     TpResponse,
     /// Tp error
     TpError,
+    /// Request ack (older specification revisions, lenient parsing only)
+    RequestAck,
+    /// Request no return ack (older specification revisions, lenient parsing only)
+    RequestNoReturnAck,
+    /// Notification ack (older specification revisions, lenient parsing only)
+    NotificationAck,
+    /// Response ack (older specification revisions, lenient parsing only)
+    ResponseAck,
+    /// Error ack (older specification revisions, lenient parsing only)
+    ErrorAck,
+    /// Unknown message type value (lenient parsing only)
+    ///
+    /// Compares equal to the known variant encoding to the same value.
+    Unknown(u8),
+}
+
+impl PartialEq for MessageType {
+    fn eq(&self, other: &Self) -> bool {
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Eq for MessageType {}
+
+impl Hash for MessageType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state)
+    }
+}
+
+impl MessageType {
+    /// Transforms a u8 representing the type to a MessageType.
+    ///
+    /// In lenient mode, the ack types of older specification revisions
+    /// and unknown values are accepted instead of failing.
+    #[inline]
+    pub fn from_u8(value: u8, mode: ParseMode) -> Result<Self, Error> {
+        match mode {
+            ParseMode::Strict => Self::try_from(value),
+            ParseMode::Lenient => Ok(match value {
+                0x40 => Self::RequestAck,
+                0x41 => Self::RequestNoReturnAck,
+                0x42 => Self::NotificationAck,
+                0xc0 => Self::ResponseAck,
+                0xc1 => Self::ErrorAck,
+                value => Self::try_from(value).unwrap_or(Self::Unknown(value)),
+            }),
+        }
+    }
+
+    /// The variant encoding to the same value, `Unknown` only for values without one.
+    #[inline]
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::Unknown(value) => Self::from_u8(value, ParseMode::Lenient).unwrap_or(self),
+            message_type => message_type,
+        }
+    }
 }
 
 /// Different kinds of EntriesTyp accepted in a SdPayload.
@@ -298,6 +454,7 @@ pub enum EntriesType {
 impl TryFrom<u8> for MessageType {
     type Error = Error;
 
+    #[inline]
     fn try_from(i: u8) -> Result<Self, Error> {
         match i {
             0x00 => Ok(Self::Request),
@@ -330,12 +487,22 @@ impl From<MessageType> for u8 {
             TpNotification => 0x22,
             TpResponse => 0xa0,
             TpError => 0xa1,
+            RequestAck => 0x40,
+            RequestNoReturnAck => 0x41,
+            NotificationAck => 0x42,
+            ResponseAck => 0xc0,
+            ErrorAck => 0xc1,
+            Unknown(i) => i,
         }
     }
 }
 
 /// Different kinds of ReturnCode accepted in a header.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+///
+/// Values are compared by the byte they encode to, so `Unknown(0x00)`
+/// equals `Ok`. Match on [`ReturnCode::normalize`] to treat them alike.
+#[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub enum ReturnCode {
     /// No error occurred
     Ok,
@@ -363,12 +530,53 @@ pub enum ReturnCode {
     ReservedGeneric(u8),
     /// Reserved for specific errors of services and meth- ods. These errors are specified by the interface specification.
     ReservedSpecific(u8),
+    /// Unknown return code value (lenient parsing only)
+    ///
+    /// Compares equal to the known variant encoding to the same value.
+    Unknown(u8),
+}
+
+impl PartialEq for ReturnCode {
+    fn eq(&self, other: &Self) -> bool {
+        u8::from(*self) == u8::from(*other)
+    }
+}
+
+impl Eq for ReturnCode {}
+
+impl Hash for ReturnCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u8::from(*self).hash(state)
+    }
+}
+
+impl ReturnCode {
+    /// Transforms a u8 representing the code to a ReturnCode.
+    ///
+    /// In lenient mode, values outside the specified ranges are accepted instead of failing.
+    #[inline]
+    pub fn from_u8(value: u8, mode: ParseMode) -> Result<Self, Error> {
+        match mode {
+            ParseMode::Strict => Self::try_from(value),
+            ParseMode::Lenient => Ok(Self::try_from(value).unwrap_or(Self::Unknown(value))),
+        }
+    }
+
+    /// The variant encoding to the same value, `Unknown` only for values without one.
+    #[inline]
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::Unknown(value) => Self::from_u8(value, ParseMode::Lenient).unwrap_or(self),
+            return_code => return_code,
+        }
+    }
 }
 
 /// Transforms a u8 representing the type to a ReturnCode.
 impl TryFrom<u8> for ReturnCode {
     type Error = Error;
 
+    #[inline]
     fn try_from(i: u8) -> Result<Self, Self::Error> {
         match i {
             0x00 => Ok(Self::Ok),
@@ -407,6 +615,7 @@ impl From<ReturnCode> for u8 {
             WrongMessageType => 0x0a,
             ReservedGeneric(i) => i,
             ReservedSpecific(i) => i,
+            Unknown(i) => i,
         }
     }
 }
@@ -414,6 +623,7 @@ impl From<ReturnCode> for u8 {
 /// Represents the RpcPayload within a RPC message.
 pub type RpcPayload<'a> = &'a [u8];
 
+#[cfg(feature = "alloc")]
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(clippy::len_without_is_empty)]
 /// Represents the SdPayload within a SD message.
@@ -426,6 +636,7 @@ pub struct SdPayload {
     pub options: Vec<SdOption>,
 }
 
+#[cfg(feature = "alloc")]
 impl SdPayload {
     /// Length of the payload in bytes
     pub fn len(&self) -> usize {
@@ -681,6 +892,7 @@ impl SdOption {
     }
 
     /// Length of the option in bytes
+    #[cfg(feature = "alloc")]
     pub(crate) fn len(&self) -> usize {
         use SdOption::*;
         match self {
@@ -835,14 +1047,104 @@ mod test {
                     (error, Err(Error::InvalidReturnCode(error))),
                 ];
                 for (input, expected) in values.iter() {
-                    let result = ReturnCode::try_from(*input);
-                    // TODO: Error cannot implmenet PartialEq because of io::Error
-                    assert_eq!(format!("{:?}", result), format!("{:?}", expected));
+                    assert_eq!(&ReturnCode::try_from(*input), expected);
                 }
             }
         }
     }
 
+    #[test]
+    fn message_type_from_u8() {
+        for value in 0x00..=0xFF {
+            let strict = MessageType::from_u8(value, ParseMode::Strict);
+            let lenient = MessageType::from_u8(value, ParseMode::Lenient).unwrap();
+            assert_eq!(value, u8::from(lenient));
+
+            match strict {
+                Ok(message_type) => assert_eq!(message_type, lenient),
+                Err(_) => assert!(matches!(
+                    lenient,
+                    MessageType::RequestAck
+                        | MessageType::RequestNoReturnAck
+                        | MessageType::NotificationAck
+                        | MessageType::ResponseAck
+                        | MessageType::ErrorAck
+                        | MessageType::Unknown(_)
+                )),
+            }
+        }
+
+        assert_eq!(
+            Ok(MessageType::ErrorAck),
+            MessageType::from_u8(0xC1, ParseMode::Lenient).map_err(|e| e.to_string())
+        );
+    }
+
+    #[test]
+    fn return_code_from_u8() {
+        for value in 0x00..=0xFF {
+            let strict = ReturnCode::from_u8(value, ParseMode::Strict);
+            let lenient = ReturnCode::from_u8(value, ParseMode::Lenient).unwrap();
+            assert_eq!(value, u8::from(lenient));
+
+            if value < 0x5f {
+                assert_eq!(strict.unwrap(), lenient);
+            } else {
+                assert!(strict.is_err());
+                assert_eq!(ReturnCode::Unknown(value), lenient);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_values_compare_by_encoding() {
+        assert_eq!(MessageType::Unknown(0x00), MessageType::Request);
+        assert_eq!(MessageType::Unknown(0x40), MessageType::RequestAck);
+        assert_ne!(MessageType::Unknown(0x03), MessageType::Request);
+        assert_eq!(ReturnCode::Unknown(0x00), ReturnCode::Ok);
+        assert_eq!(ReturnCode::Unknown(0x0b), ReturnCode::ReservedGeneric(0x0b));
+        assert_ne!(ReturnCode::Unknown(0x5f), ReturnCode::Ok);
+
+        let types: std::collections::HashSet<_> =
+            [MessageType::Unknown(0x80), MessageType::Response].into();
+        assert_eq!(1, types.len());
+    }
+
+    #[test]
+    fn normalize_unknown_values() {
+        assert!(matches!(
+            MessageType::Unknown(0x02).normalize(),
+            MessageType::Notification
+        ));
+        assert!(matches!(
+            MessageType::Unknown(0x42).normalize(),
+            MessageType::NotificationAck
+        ));
+        assert!(matches!(
+            MessageType::Unknown(0x03).normalize(),
+            MessageType::Unknown(0x03)
+        ));
+        assert!(matches!(
+            ReturnCode::Unknown(0x0b).normalize(),
+            ReturnCode::ReservedGeneric(0x0b)
+        ));
+        assert!(matches!(
+            ReturnCode::Unknown(0x5f).normalize(),
+            ReturnCode::Unknown(0x5f)
+        ));
+
+        let header = Header::new(
+            MessageId::new(0xFFFF, 0x8100),
+            20,
+            RequestId::new(0x0000, 0x0001),
+            0x01,
+            0x01,
+            MessageType::Unknown(0x02),
+            ReturnCode::Unknown(0x00),
+        );
+        assert!(header.is_sd());
+    }
+
     #[test]
     fn header_builder_smoke() {
         super::HeaderBuilder::default()
@@ -857,6 +1159,35 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn message_buf_outlives_input() {
+        let message = {
+            let input = vec![
+                0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+                0x00, 0x00, 0x00, 0x0A, // length(u32)
+                0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+                0x01, 0x01, 0x02,
+                0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+                0x01, 0x02, // payload
+            ];
+            MessageBuf::from_slice(&input).unwrap()
+        };
+
+        assert_eq!(0x0103, message.header().unwrap().message_id.service_id);
+        match message.as_message() {
+            Message::Rpc(header, payload) => {
+                assert_eq!(10, header.length);
+                assert_eq!(&[0x01, 0x02], payload);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let roundtrip = MessageBuf::from(Message::from(&message));
+        assert_eq!(message, roundtrip);
+        assert_eq!(MessageBuf::CookieClient, Message::CookieClient.into());
+        assert_eq!(None, MessageBuf::CookieServer.header());
+    }
+
     #[allow(unused)]
     fn sd_payload() {
         let payload = SdPayload {
@@ -928,12 +1259,12 @@ mod test {
 
         let options = payload.options(0);
         assert_eq!(2, options.len());
-        assert_eq!(30000, options.get(0).unwrap().port);
+        assert_eq!(30000, options.first().unwrap().port);
         assert_eq!(30001, options.get(1).unwrap().port);
 
         let options = payload.options(1);
         assert_eq!(2, options.len());
-        assert_eq!(30002, options.get(0).unwrap().port);
+        assert_eq!(30002, options.first().unwrap().port);
         assert_eq!(30003, options.get(1).unwrap().port);
     }
 
@@ -0,0 +1,180 @@
+use crate::{
+    serializer::{MAGIC_COOKIE_CLIENT, MAGIC_COOKIE_SERVER},
+    types::*,
+    Error,
+};
+use alloc::borrow::Cow;
+
+#[cfg(feature = "std")]
+use std::io::{ErrorKind, IoSlice, Write};
+
+/// A message prepared for vectored writes.
+///
+/// Holds the encoded header and the payload, which is borrowed from
+/// RPC messages and encoded for SD messages.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VectoredMessage<'a> {
+    header: [u8; Header::LENGTH],
+    payload: Cow<'a, [u8]>,
+}
+
+impl<'a> VectoredMessage<'a> {
+    /// The encoded header
+    pub fn header(&self) -> &[u8; Header::LENGTH] {
+        &self.header
+    }
+
+    /// The payload
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Length of the serialized message in bytes
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        Header::LENGTH + self.payload.len()
+    }
+
+    /// The header and the payload as slices for `write_vectored` or `sendmsg`.
+    #[cfg(feature = "std")]
+    pub fn io_slices(&self) -> [IoSlice<'_>; 2] {
+        [IoSlice::new(&self.header), IoSlice::new(&self.payload)]
+    }
+
+    /// Write the message into a writer using vectored writes.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<usize, Error> {
+        let mut slices = self.io_slices();
+        let mut slices = &mut slices[..];
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => return Err(Error::Io(ErrorKind::WriteZero)),
+                Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(self.len())
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Prepare the message for vectored writes without copying an RPC payload.
+    pub fn to_vectored(&self) -> Result<VectoredMessage<'a>, Error> {
+        self.validate()?;
+        Ok(match self {
+            Message::Rpc(header, payload) => VectoredMessage {
+                header: header.to_bytes(),
+                payload: Cow::Borrowed(payload),
+            },
+            Message::Sd(header, payload) => {
+                let mut buffer = alloc::vec![0u8; payload.len()];
+                payload.to_slice(&mut buffer).unwrap(); // Safe because the length is precomputed
+                VectoredMessage {
+                    header: header.to_bytes(),
+                    payload: Cow::Owned(buffer),
+                }
+            }
+            Message::CookieClient => VectoredMessage {
+                header: MAGIC_COOKIE_CLIENT.to_bytes(),
+                payload: Cow::Borrowed(&[]),
+            },
+            Message::CookieServer => VectoredMessage {
+                header: MAGIC_COOKIE_SERVER.to_bytes(),
+                payload: Cow::Borrowed(&[]),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RPC_MESSAGE: &[u8] = &[
+        0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x0D, // length(u32)
+        0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+        0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+        0x01, 0x02, 0x03, 0x04, 0x05, // payload
+    ];
+
+    /// Writer accepting at most three bytes per call
+    struct ChunkedWriter(Vec<u8>);
+
+    impl Write for ChunkedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn vectored_rpc_message() {
+        let message = Message::from_slice(RPC_MESSAGE).unwrap();
+        let vectored = message.to_vectored().unwrap();
+
+        assert_eq!(&RPC_MESSAGE[..16], vectored.header());
+        assert_eq!(RPC_MESSAGE[16..].as_ptr(), vectored.payload().as_ptr());
+        assert_eq!(21, vectored.len());
+
+        let slices = vectored.io_slices();
+        assert_eq!(RPC_MESSAGE, [&*slices[0], &*slices[1]].concat());
+
+        let mut writer = ChunkedWriter(Vec::new());
+        assert_eq!(21, vectored.write_to(&mut writer).unwrap());
+        assert_eq!(RPC_MESSAGE, writer.0);
+    }
+
+    #[test]
+    fn vectored_sd_message_and_cookies() {
+        let message = Message::Sd(
+            Header::new(
+                MessageId::new(0xFFFF, 0x8100),
+                20,
+                RequestId::new(0x0000, 0x0001),
+                0x01,
+                0x01,
+                MessageType::Notification,
+                ReturnCode::Ok,
+            ),
+            SdPayload {
+                flags: 0xC0,
+                entries: Vec::new(),
+                options: Vec::new(),
+            },
+        );
+        let vectored = message.to_vectored().unwrap();
+        assert_eq!(12, vectored.payload().len());
+        assert_eq!(
+            message.to_vec().unwrap(),
+            [vectored.header(), vectored.payload()].concat()
+        );
+
+        // SD payloads are streamed into the writer
+        let mut writer = ChunkedWriter(Vec::new());
+        assert_eq!(28, message.to_writer(&mut writer).unwrap());
+        assert_eq!(message.to_vec().unwrap(), writer.0);
+
+        let vectored = Message::CookieServer.to_vectored().unwrap();
+        assert!(vectored.payload().is_empty());
+        assert_eq!(Message::CookieServer.to_vec().unwrap(), vectored.header());
+    }
+
+    #[test]
+    fn vectored_write_zero() {
+        let mut buffer = [0u8; 20];
+        let message = Message::from_slice(RPC_MESSAGE).unwrap();
+
+        assert_eq!(
+            Err(Error::Io(ErrorKind::WriteZero)),
+            message.to_vectored().unwrap().write_to(&mut buffer[..])
+        );
+    }
+}
@@ -0,0 +1,524 @@
+use crate::error::{Context, PathSegment::Field};
+use crate::{types::*, Error};
+
+/// Serialized magic cookie sent by clients
+pub(crate) const MAGIC_COOKIE_CLIENT_BYTES: [u8; Header::LENGTH] = [
+    0xFF, 0xFF, 0x00, 0x00, // serviceId(u16), methodId(u16)
+    0x00, 0x00, 0x00, 0x08, // length(u32)
+    0xDE, 0xAD, 0xBE, 0xEF, // clientId(u16), sessionId(u16)
+    0x01, 0x01, 0x01, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+];
+
+/// Serialized magic cookie sent by servers
+pub(crate) const MAGIC_COOKIE_SERVER_BYTES: [u8; Header::LENGTH] = [
+    0xFF, 0xFF, 0x80, 0x00, // serviceId(u16), methodId(u16)
+    0x00, 0x00, 0x00, 0x08, // length(u32)
+    0xDE, 0xAD, 0xBE, 0xEF, // clientId(u16), sessionId(u16)
+    0x01, 0x01, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+];
+
+/// Borrowed view of a serialized header which decodes its fields on access.
+///
+/// Numeric fields are read directly from the bytes and never fail,
+/// only [`HeaderRef::message_type`] and [`HeaderRef::return_code`] validate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HeaderRef<'a> {
+    bytes: &'a [u8; Header::LENGTH],
+}
+
+impl<'a> HeaderRef<'a> {
+    /// Creates a view of the given header bytes.
+    pub const fn new(bytes: &'a [u8; Header::LENGTH]) -> Self {
+        Self { bytes }
+    }
+
+    /// Creates a view of the header at the start of a byte slice.
+    #[inline]
+    pub fn from_slice(input: &'a [u8]) -> Result<Self, Error> {
+        match input.first_chunk() {
+            Some(bytes) => Ok(Self::new(bytes)),
+            None => Err(Error::NotEnoughData {
+                min: Header::LENGTH,
+                actual: input.len(),
+            }),
+        }
+    }
+
+    /// The underlying header bytes
+    pub const fn as_bytes(&self) -> &'a [u8; Header::LENGTH] {
+        self.bytes
+    }
+
+    const fn u16_at(&self, offset: usize) -> u16 {
+        u16::from_be_bytes([self.bytes[offset], self.bytes[offset + 1]])
+    }
+
+    /// Get service id
+    pub const fn service_id(&self) -> ServiceId {
+        self.u16_at(0)
+    }
+
+    /// Get method id
+    pub const fn method_id(&self) -> MethodId {
+        self.u16_at(2)
+    }
+
+    /// Get message id
+    pub const fn message_id(&self) -> MessageId {
+        MessageId::new(self.service_id(), self.method_id())
+    }
+
+    /// Raw length field
+    pub const fn length(&self) -> u32 {
+        u32::from_be_bytes([self.bytes[4], self.bytes[5], self.bytes[6], self.bytes[7]])
+    }
+
+    /// The length of the message associated with the header
+    ///
+    /// Saturates at `usize::MAX` on 32-bit targets.
+    pub const fn message_len(&self) -> usize {
+        (self.length() as usize).saturating_add(8)
+    }
+
+    /// The length of the payload associated with the header (zero if the length field is invalid)
+    pub const fn payload_len(&self) -> usize {
+        (self.length() as usize).saturating_sub(8)
+    }
+
+    /// Get client id
+    pub const fn client_id(&self) -> ClientId {
+        self.u16_at(8)
+    }
+
+    /// Get session id
+    pub const fn session_id(&self) -> SessionId {
+        self.u16_at(10)
+    }
+
+    /// Get request id
+    pub const fn request_id(&self) -> RequestId {
+        RequestId::new(self.client_id(), self.session_id())
+    }
+
+    /// Get protocol version
+    pub const fn protocol_version(&self) -> ProtocolVersion {
+        self.bytes[12]
+    }
+
+    /// Get interface version
+    pub const fn interface_version(&self) -> InterfaceVersion {
+        self.bytes[13]
+    }
+
+    /// Raw message type field
+    pub const fn raw_message_type(&self) -> u8 {
+        self.bytes[14]
+    }
+
+    /// Raw return code field
+    pub const fn raw_return_code(&self) -> u8 {
+        self.bytes[15]
+    }
+
+    /// Get message type
+    pub fn message_type(&self) -> Result<MessageType, Error> {
+        MessageType::try_from(self.raw_message_type())
+    }
+
+    /// Get return code
+    pub fn return_code(&self) -> Result<ReturnCode, Error> {
+        ReturnCode::try_from(self.raw_return_code())
+    }
+
+    /// Returns true if the header indicates a SD message
+    pub const fn is_sd(&self) -> bool {
+        self.service_id() == 0xFFFF
+            && self.method_id() == 0x8100
+            && self.protocol_version() == 0x01
+            && self.interface_version() == 0x01
+            && self.raw_message_type() == 0x02
+            && self.raw_return_code() == 0x00
+    }
+
+    /// Returns true if the header is the magic cookie sent by clients
+    pub fn is_cookie_client(&self) -> bool {
+        *self.bytes == MAGIC_COOKIE_CLIENT_BYTES
+    }
+
+    /// Returns true if the header is the magic cookie sent by servers
+    pub fn is_cookie_server(&self) -> bool {
+        *self.bytes == MAGIC_COOKIE_SERVER_BYTES
+    }
+
+    /// Decode all fields into a [`Header`].
+    #[inline]
+    pub fn to_header(&self) -> Result<Header, Error> {
+        self.to_header_with_mode(ParseMode::Strict)
+    }
+
+    /// Decode all fields into a [`Header`] using the given parse mode.
+    #[inline]
+    pub fn to_header_with_mode(&self, mode: ParseMode) -> Result<Header, Error> {
+        self.decode(mode).context(0, Field("header"))
+    }
+
+    #[inline]
+    fn check_length(&self) -> Result<(), Error> {
+        let length = self.length();
+        if length < if self.is_sd() { 20 } else { 8 } {
+            return Err(Error::InvalidLengthField(length));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn decode(&self, mode: ParseMode) -> Result<Header, Error> {
+        let message_type = MessageType::from_u8(self.raw_message_type(), mode)
+            .context(14, Field("message_type"))?;
+        let return_code =
+            ReturnCode::from_u8(self.raw_return_code(), mode).context(15, Field("return_code"))?;
+
+        self.check_length().context(4, Field("length"))?;
+
+        Ok(Header {
+            message_id: self.message_id(),
+            length: self.length(),
+            request_id: self.request_id(),
+            protocol_version: self.protocol_version(),
+            interface_version: self.interface_version(),
+            message_type,
+            return_code,
+        })
+    }
+}
+
+impl<'a> From<&'a [u8; Header::LENGTH]> for HeaderRef<'a> {
+    fn from(bytes: &'a [u8; Header::LENGTH]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+/// Mutable view of a serialized header which patches its fields in place.
+///
+/// The header is validated when the view is created and every setter
+/// rejects values which would make it invalid, leaving the bytes unchanged.
+/// A view created from a message buffer also rejects lengths exceeding the buffer.
+#[derive(Debug, Eq, PartialEq)]
+pub struct HeaderMut<'a> {
+    bytes: &'a mut [u8; Header::LENGTH],
+    /// Number of bytes following the header, if the view covers a message buffer
+    payload_len: Option<usize>,
+    mode: ParseMode,
+}
+
+impl<'a> HeaderMut<'a> {
+    /// Creates a view of the given header bytes.
+    pub fn new(bytes: &'a mut [u8; Header::LENGTH]) -> Result<Self, Error> {
+        Self::new_with_mode(bytes, ParseMode::Strict)
+    }
+
+    /// Creates a view of the given header bytes using the given parse mode.
+    ///
+    /// The mode also applies to the values passed to the setters.
+    pub fn new_with_mode(
+        bytes: &'a mut [u8; Header::LENGTH],
+        mode: ParseMode,
+    ) -> Result<Self, Error> {
+        HeaderRef::new(bytes).to_header_with_mode(mode)?;
+        Ok(Self {
+            bytes,
+            payload_len: None,
+            mode,
+        })
+    }
+
+    /// Creates a view of the header at the start of a byte slice, e.g. a message buffer.
+    pub fn from_slice(input: &'a mut [u8]) -> Result<Self, Error> {
+        Self::from_slice_with_mode(input, ParseMode::Strict)
+    }
+
+    /// Creates a view of the header at the start of a byte slice using the given parse mode.
+    ///
+    /// The rest of the slice is the space available for the payload.
+    pub fn from_slice_with_mode(input: &'a mut [u8], mode: ParseMode) -> Result<Self, Error> {
+        let actual = input.len();
+        match input.split_first_chunk_mut() {
+            Some((bytes, payload)) => Ok(Self {
+                payload_len: Some(payload.len()),
+                ..Self::new_with_mode(bytes, mode)?
+            }),
+            None => Err(Error::NotEnoughData {
+                min: Header::LENGTH,
+                actual,
+            }),
+        }
+    }
+
+    /// Read only view of the header
+    pub fn as_header_ref(&self) -> HeaderRef<'_> {
+        HeaderRef::new(self.bytes)
+    }
+
+    /// Writes `value` at `offset` if the resulting header has a valid length field.
+    fn patch(&mut self, offset: usize, value: &[u8]) -> Result<(), Error> {
+        let mut bytes = *self.bytes;
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+        HeaderRef::new(&bytes).check_length()?;
+        *self.bytes = bytes;
+        Ok(())
+    }
+
+    /// Set service id
+    pub fn set_service_id(&mut self, service_id: ServiceId) -> Result<(), Error> {
+        self.patch(0, &service_id.to_be_bytes())
+    }
+
+    /// Set method id
+    pub fn set_method_id(&mut self, method_id: MethodId) -> Result<(), Error> {
+        self.patch(2, &method_id.to_be_bytes())
+    }
+
+    /// Set message id
+    pub fn set_message_id(&mut self, message_id: &MessageId) -> Result<(), Error> {
+        let mut value = [0u8; 4];
+        value[..2].copy_from_slice(&message_id.service_id.to_be_bytes());
+        value[2..].copy_from_slice(&message_id.method_id.to_be_bytes());
+        self.patch(0, &value)
+    }
+
+    /// Set the raw length field
+    ///
+    /// For a view of a message buffer, the message must fit into the buffer.
+    pub fn set_length(&mut self, length: u32) -> Result<(), Error> {
+        if let Some(payload_len) = self.payload_len {
+            if u64::from(length) > payload_len as u64 + 8 {
+                return Err(Error::NotEnoughData {
+                    min: usize::try_from(u64::from(length) + 8).unwrap_or(usize::MAX),
+                    actual: Header::LENGTH + payload_len,
+                });
+            }
+        }
+        self.patch(4, &length.to_be_bytes())
+    }
+
+    /// Set client id
+    pub fn set_client_id(&mut self, client_id: ClientId) -> Result<(), Error> {
+        self.patch(8, &client_id.to_be_bytes())
+    }
+
+    /// Set session id
+    pub fn set_session_id(&mut self, session_id: SessionId) -> Result<(), Error> {
+        self.patch(10, &session_id.to_be_bytes())
+    }
+
+    /// Set request id
+    pub fn set_request_id(&mut self, request_id: &RequestId) -> Result<(), Error> {
+        let mut value = [0u8; 4];
+        value[..2].copy_from_slice(&request_id.client_id.to_be_bytes());
+        value[2..].copy_from_slice(&request_id.session_id.to_be_bytes());
+        self.patch(8, &value)
+    }
+
+    /// Set protocol version
+    pub fn set_protocol_version(&mut self, protocol_version: ProtocolVersion) -> Result<(), Error> {
+        self.patch(12, &[protocol_version])
+    }
+
+    /// Set interface version
+    pub fn set_interface_version(
+        &mut self,
+        interface_version: InterfaceVersion,
+    ) -> Result<(), Error> {
+        self.patch(13, &[interface_version])
+    }
+
+    /// Set message type
+    ///
+    /// In strict mode, the ack types and unknown values are rejected.
+    pub fn set_message_type(&mut self, message_type: MessageType) -> Result<(), Error> {
+        let value = u8::from(message_type);
+        MessageType::from_u8(value, self.mode)?;
+        self.patch(14, &[value])
+    }
+
+    /// Set return code
+    ///
+    /// In strict mode, unknown values are rejected.
+    pub fn set_return_code(&mut self, return_code: ReturnCode) -> Result<(), Error> {
+        let value = u8::from(return_code);
+        ReturnCode::from_u8(value, self.mode)?;
+        self.patch(15, &[value])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HEADER: &[u8; 16] = &[
+        0x01, 0x03, 0x80, 0x05, // serviceId(u16), methodId(u16)
+        0x00, 0x00, 0x00, 0x0D, // length(u32)
+        0x00, 0x01, 0x00, 0x02, // clientId(u16), sessionId(u16)
+        0x01, 0x02, 0x02, 0x00, // proto(u8), version(u8), messageType,(u8) returnCode(u8)
+    ];
+
+    #[test]
+    fn header_ref_fields() {
+        const VIEW: HeaderRef = HeaderRef::new(HEADER);
+        const MESSAGE_ID: MessageId = VIEW.message_id();
+
+        assert_eq!(MessageId::new(0x0103, 0x8005), MESSAGE_ID);
+        assert_eq!(13, VIEW.length());
+        assert_eq!(21, VIEW.message_len());
+        assert_eq!(5, VIEW.payload_len());
+        assert_eq!(RequestId::new(0x0001, 0x0002), VIEW.request_id());
+
+        let mut bytes = *HEADER;
+        bytes[4..8].copy_from_slice(&[0xFF; 4]);
+        assert_eq!(
+            usize::try_from(0x1_0000_0007u64).unwrap_or(usize::MAX),
+            HeaderRef::new(&bytes).message_len()
+        );
+        assert_eq!(1, VIEW.protocol_version());
+        assert_eq!(2, VIEW.interface_version());
+        assert_eq!(Ok(MessageType::Notification), VIEW.message_type());
+        assert_eq!(Ok(ReturnCode::Ok), VIEW.return_code());
+        assert!(!VIEW.is_sd());
+        assert_eq!(Header::from_slice(HEADER), VIEW.to_header());
+    }
+
+    #[test]
+    fn header_ref_from_slice() {
+        let input = [HEADER.as_slice(), &[0x01, 0x02, 0x03]].concat();
+        let view = HeaderRef::from_slice(&input).unwrap();
+        assert_eq!(HEADER, view.as_bytes());
+
+        assert_eq!(
+            Err(Error::NotEnoughData {
+                min: 16,
+                actual: 15
+            }),
+            HeaderRef::from_slice(&HEADER[..15])
+        );
+    }
+
+    #[test]
+    fn header_ref_cookies() {
+        assert!(HeaderRef::new(&MAGIC_COOKIE_CLIENT_BYTES).is_cookie_client());
+        assert!(!HeaderRef::new(&MAGIC_COOKIE_CLIENT_BYTES).is_cookie_server());
+        assert!(HeaderRef::new(&MAGIC_COOKIE_SERVER_BYTES).is_cookie_server());
+        assert!(!HeaderRef::new(HEADER).is_cookie_client());
+    }
+
+    #[test]
+    fn header_ref_invalid_fields() {
+        let mut bytes = *HEADER;
+        bytes[15] = 0xFF;
+        let view = HeaderRef::new(&bytes);
+
+        assert_eq!(Err(Error::InvalidReturnCode(0xFF)), view.return_code());
+        let error = view.to_header().unwrap_err();
+        assert_eq!(Some(15), error.offset());
+        assert_eq!("header.return_code", error.path().unwrap().to_string());
+        assert_eq!(
+            Ok(ReturnCode::Unknown(0xFF)),
+            view.to_header_with_mode(ParseMode::Lenient)
+                .map(|header| header.return_code)
+        );
+
+        bytes[7] = 0x07;
+        bytes[15] = 0x00;
+        let view = HeaderRef::new(&bytes);
+        assert_eq!(0, view.payload_len());
+        assert_eq!(
+            &Error::InvalidLengthField(7),
+            view.to_header().unwrap_err().root_cause()
+        );
+    }
+
+    #[test]
+    fn header_mut_setters() {
+        let mut message = [HEADER.as_slice(), &[0x01, 0x02, 0x03, 0x04, 0x05]].concat();
+        let mut header = HeaderMut::from_slice(&mut message).unwrap();
+
+        header
+            .set_message_id(&MessageId::new(0x0104, 0x0001))
+            .unwrap();
+        header.set_method_id(0x0002).unwrap();
+        header
+            .set_request_id(&RequestId::new(0x0010, 0x0020))
+            .unwrap();
+        header.set_session_id(0x0021).unwrap();
+        header.set_interface_version(0x03).unwrap();
+        header.set_message_type(MessageType::Response).unwrap();
+        header.set_return_code(ReturnCode::NotReady).unwrap();
+        header.set_length(12).unwrap();
+        assert_eq!(0x0104, header.as_header_ref().service_id());
+
+        // the message must fit into the buffer
+        assert_eq!(
+            Err(Error::NotEnoughData {
+                min: 22,
+                actual: 21
+            }),
+            header.set_length(14)
+        );
+        header.set_length(13).unwrap();
+        header.set_length(12).unwrap();
+
+        assert_eq!(
+            Message::Rpc(
+                Header::new(
+                    MessageId::new(0x0104, 0x0002),
+                    12,
+                    RequestId::new(0x0010, 0x0021),
+                    0x01,
+                    0x03,
+                    MessageType::Response,
+                    ReturnCode::NotReady,
+                ),
+                &[0x01, 0x02, 0x03, 0x04]
+            ),
+            Message::from_slice(&message).unwrap()
+        );
+    }
+
+    #[test]
+    fn header_mut_validation() {
+        let mut bytes = *HEADER;
+        let mut header = HeaderMut::new(&mut bytes).unwrap();
+
+        assert_eq!(Err(Error::InvalidLengthField(7)), header.set_length(7));
+        assert_eq!(
+            Err(Error::InvalidMessageType(0x40)),
+            header.set_message_type(MessageType::RequestAck)
+        );
+        assert_eq!(
+            Err(Error::InvalidReturnCode(0x60)),
+            header.set_return_code(ReturnCode::Unknown(0x60))
+        );
+        assert_eq!(HEADER, header.as_header_ref().as_bytes());
+
+        // turning the header into an SD header requires a length of at least 20
+        header.set_interface_version(0x01).unwrap();
+        assert_eq!(
+            Err(Error::InvalidLengthField(13)),
+            header.set_message_id(&MessageId::new(0xFFFF, 0x8100))
+        );
+        assert_eq!(
+            MessageId::new(0x0103, 0x8005),
+            header.as_header_ref().message_id()
+        );
+
+        let mut header = HeaderMut::new_with_mode(&mut bytes, ParseMode::Lenient).unwrap();
+        header.set_message_type(MessageType::RequestAck).unwrap();
+        assert_eq!(0x40, bytes[14]);
+
+        assert_eq!(
+            Err(Error::NotEnoughData { min: 16, actual: 4 }),
+            HeaderMut::from_slice(&mut bytes[..4])
+        );
+        bytes[15] = 0x60;
+        assert!(HeaderMut::new(&mut bytes).is_err());
+    }
+}